@@ -34,8 +34,20 @@ macro_rules! tsy_lines {
 // re-export macros (note pub)
 use serde::ser::Serializer;
 use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+use std::path::Path;
+use typescript_definitions_ast::Item;
 pub use typescript_definitions_derive::*;
 
+pub use typescript_definitions_ast::{Quote, Separator, TsFormat};
+
+pub mod testing;
+
+// used by the code generated by `TypeScriptify` to register types
+#[doc(hidden)]
+pub use inventory;
+
 /// # Trait implemented by `TypeScriptify` derive macro.
 ///
 /// Please see documentation at [crates.io](https://crates.io/crates/typescript-definitions)
@@ -45,6 +57,121 @@ pub use typescript_definitions_derive::*;
 pub trait TypeScriptifyTrait {
     fn type_script_ify() -> Cow<'static, str>;
 
+    /// A typescript [type guard](https://www.typescriptlang.org/docs/handbook/advanced-types.html)
+    /// that checks at runtime that a value (e.g. from `JSON.parse`) is of this type.
+    ///
+    /// Generic types take an extra guard argument for each type parameter.
+    ///
+    /// An `Err` if `guard = false` is in the `[outputs]` of `typescript-definitions.toml`.
+    ///
+    /// Example:
+    ///
+    /// Input
+    /// ```ignore
+    /// #[derive(Serialize, TypeScriptify)]
+    /// struct Value<T> { value: T, tags: Vec<String> }
+    /// ```
+    /// Output
+    /// ```typescript
+    /// export function isValue<T>(obj: any, isT: (obj: any) => obj is T): obj is Value<T> {
+    ///     return typeof obj === "object" && !(obj === null) && isT(obj.value)
+    ///         && Array.isArray(obj.tags) && obj.tags.every((e0: any) => typeof e0 === "string");
+    /// }
+    /// ```
+    fn type_script_guard() -> Result<Cow<'static, str>, &'static str> {
+        Err("no guard was derived")
+    }
+
+    /// A [zod](https://zod.dev) schema for this type named `{Name}Schema`. It needs
+    /// `import { z } from "zod"` and the schemas of the types it references.
+    ///
+    /// Enums with a serde `tag` are a `z.discriminatedUnion` and generic types
+    /// are a function of their type parameters' schemas.
+    ///
+    /// An `Err` if `zod = false` is in the `[outputs]` of `typescript-definitions.toml`.
+    ///
+    /// Example:
+    ///
+    /// Input
+    /// ```ignore
+    /// #[derive(Serialize, TypeScriptify)]
+    /// #[serde(tag = "kind")]
+    /// enum Shape { Circle { center: Point, radius: f64 }, Empty }
+    /// ```
+    /// Output
+    /// ```typescript
+    /// export const ShapeSchema: z.ZodType<Shape> = z.discriminatedUnion("kind", [z.object({ kind: z.literal("Circle"), center: z.lazy(() => PointSchema), radius: z.number() }), z.object({ kind: z.literal("Empty") })])
+    /// ```
+    fn type_script_zod() -> Result<Cow<'static, str>, &'static str> {
+        Err("no zod schema was derived")
+    }
+
+    /// A [JSON Schema](https://json-schema.org) of what serde serializes, titled with
+    /// the type's name. Doc comments become `description`s and enums are a `oneOf`
+    /// of their variants.
+    ///
+    /// Other types are referenced where `dialect` keeps them: `#/$defs/Name` or
+    /// `#/components/schemas/Name`. Use [`export_json_schema`] to write a document
+    /// holding every registered type.
+    ///
+    /// An `Err` if `json_schema = false` is in the `[outputs]` of `typescript-definitions.toml`.
+    ///
+    /// Example:
+    ///
+    /// Input
+    /// ```ignore
+    /// #[derive(Serialize, TypeScriptify)]
+    /// #[serde(tag = "kind")]
+    /// enum Shape { Circle { center: Point, radius: f64 }, Empty }
+    /// ```
+    /// Output (of `Shape::json_schema(SchemaDialect::OpenApi)`)
+    /// ```json
+    /// {"discriminator":{"propertyName":"kind"},"oneOf":[{"properties":{"center":{"$ref":"#/components/schemas/Point"},"kind":{"const":"Circle"},"radius":{"type":"number"}},"required":["kind","center","radius"],"type":"object"},{"properties":{"kind":{"const":"Empty"}},"required":["kind"],"type":"object"}],"title":"Shape"}
+    /// ```
+    fn json_schema(_dialect: SchemaDialect) -> Result<Cow<'static, str>, &'static str> {
+        Err("no JSON schema was derived")
+    }
+
+    /// The names of the types this definition references (excluding builtins
+    /// and its own type parameters) e.g. `["Value"]` for
+    /// `struct Msg { borrow: Value<i32> }`. These must be exported too.
+    fn dependencies() -> &'static [&'static str] {
+        &[]
+    }
+
+    /// The shape serde *deserializes* (e.g. a request body), named `{Name}Input`.
+    ///
+    /// Fields are named by `rename(deserialize = "...")`, `skip_deserializing`
+    /// fields are left out and `#[serde(default)]` or `Option` fields are optional.
+    /// Other types are referenced by their `Input` names too.
+    ///
+    /// Empty unless the type has `#[ts(input)]` (or `input = true` in the `[defaults]`
+    /// of `typescript-definitions.toml`).
+    ///
+    /// Example:
+    ///
+    /// Input
+    /// ```ignore
+    /// #[derive(Deserialize, Serialize, TypeScriptify)]
+    /// #[ts(input)]
+    /// struct Query {
+    ///     #[serde(rename(deserialize = "q"))]
+    ///     query: String,
+    ///     #[serde(default)]
+    ///     page: u32,
+    ///     #[serde(skip_deserializing)]
+    ///     total: u32,
+    ///     filter: Option<Filter>,
+    /// }
+    /// ```
+    /// Output
+    /// ```typescript
+    /// export type QueryInput = { q: string; page?: number; filter?: FilterInput | null }
+    /// ```
+    fn type_script_input() -> Cow<'static, str> {
+        Cow::Borrowed("")
+    }
+
     #[cfg(feature = "type-enum-factories")]
     /// Available with `--features="type-enum-factories"`
     ///
@@ -65,7 +192,9 @@ pub trait TypeScriptifyTrait {
     ///     B(content: { bar: number }): R { return fn({ kind: "B", ...content }) }
     /// })
     /// ```
-    fn type_script_enum_factory() -> Result<Cow<'static, str>, &'static str>;
+    fn type_script_enum_factory() -> Result<Cow<'static, str>, &'static str> {
+        Err("no factory was derived")
+    }
 
     #[cfg(feature = "type-enum-handlers")]
     /// Available with `--features="type-enum-handlers"`
@@ -91,8 +220,303 @@ pub trait TypeScriptifyTrait {
     /// /** Apply deserialized `Foo` object to the handler `FooHandler` and return the handler's result */
     /// export function applyFoo(to: FooHandler): (outer: Foo) => void { return outer => to[outer["kind"]](outer["value"]) }
     /// ```
-    fn type_script_enum_handlers() -> Result<Cow<'static, str>, &'static str>;
+    fn type_script_enum_handlers() -> Result<Cow<'static, str>, &'static str> {
+        Err("no handlers were derived")
+    }
+}
+
+/// # The flavour of JSON Schema written by `json_schema()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaDialect {
+    /// [JSON Schema draft 2020-12](https://json-schema.org/draft/2020-12/schema):
+    /// other types are `{"$ref": "#/$defs/Name"}`.
+    JsonSchema,
+    /// An [OpenAPI 3.1](https://spec.openapis.org/oas/v3.1.0#schema-object)
+    /// `components/schemas` entry: other types are `{"$ref": "#/components/schemas/Name"}`
+    /// and tagged enums have a `discriminator`.
+    OpenApi,
 }
+
+/// # A type registered by the `TypeScriptify` derive macro.
+///
+/// Every `#[derive(TypeScriptify)]` submits one of these to a link-time
+/// registry. Use [`registered`] to list them or [`export_all`] to write them
+/// to a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypeScriptifyRegistration {
+    /// The (typescript) name of the type.
+    pub name: &'static str,
+    /// The output of `type_script_ify()` for this type.
+    pub definition: &'static str,
+    /// The syntax tree of `definition` as JSON, printed by the `_with` exports.
+    pub definition_ast: &'static str,
+    /// The output of `type_script_input()` for this type.
+    pub input: &'static str,
+    /// The syntax tree of `input` as JSON (empty if there is no input).
+    pub input_ast: &'static str,
+    /// The output of `json_schema(SchemaDialect::JsonSchema)` for this type (empty if there is none).
+    pub json_schema: &'static str,
+    /// The output of `json_schema(SchemaDialect::OpenApi)` for this type (empty if there is none).
+    pub openapi_schema: &'static str,
+    /// The output of `dependencies()` for this type.
+    pub dependencies: &'static [&'static str],
+    /// The types `input` references by their `Input` name. Those without an
+    /// `Input` type of their own are exported with an alias `type FooInput = Foo`.
+    pub input_dependencies: &'static [&'static str],
+}
+
+inventory::collect!(TypeScriptifyRegistration);
+
+/// # All the types registered by `#[derive(TypeScriptify)]`.
+///
+/// Duplicates are removed and the result is sorted by name so the
+/// output doesn't depend on link order.
+///
+/// Returns an error naming the type if two *different* types register
+/// the same name (they would clash in a single typescript file).
+pub fn registered() -> io::Result<Vec<&'static TypeScriptifyRegistration>> {
+    let mut types = BTreeMap::new();
+    for reg in inventory::iter::<TypeScriptifyRegistration> {
+        match types.insert(reg.name, reg) {
+            Some(prev) if prev.definition_ast != reg.definition_ast => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("typescript type \"{}\" is defined more than once", reg.name),
+                ))
+            }
+            _ => {}
+        }
+    }
+    Ok(types.into_values().collect())
+}
+
+/// # Write every registered typescript definition (and its `Input` shape) to `path`.
+///
+/// Example:
+///
+/// ```ignore
+/// #[derive(Serialize, TypeScriptify)]
+/// struct Point { x: i32, y: i32 }
+///
+/// typescript_definitions::export_all("bindings.ts")?;
+/// ```
+/// writes every type deriving `TypeScriptify` that is linked into
+/// the current binary (see [`registered`]).
+pub fn export_all<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    std::fs::write(path, export_all_source()?)
+}
+
+/// # [`export_all`] laid out with `format`.
+///
+/// Example:
+///
+/// ```ignore
+/// let format = TsFormat { indent: 2, quote: Quote::Single, ..TsFormat::default() };
+/// typescript_definitions::export_all_with("bindings.ts", &format)?;
+/// ```
+pub fn export_all_with<P: AsRef<Path>>(path: P, format: &TsFormat) -> io::Result<()> {
+    std::fs::write(path, export_all_source_with(format)?)
+}
+
+/// the definition and, if there is one, the `Input` type, printed from their
+/// syntax trees with `format`. Without an `Input` type, `alias` writes
+/// `type FooInput = Foo` for the `Input` types that reference us.
+fn write_registration(
+    out: &mut String,
+    reg: &TypeScriptifyRegistration,
+    format: &TsFormat,
+    alias: bool,
+) -> io::Result<()> {
+    let parse = |ast: &str| -> io::Result<Vec<Item>> {
+        serde_json::from_str(ast).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("typescript type \"{}\": {}", reg.name, e),
+            )
+        })
+    };
+    let mut write = |items: &[Item]| {
+        out.push_str(&typescript_definitions_ast::print(items, format));
+        out.push('\n');
+    };
+    let definition = parse(reg.definition_ast)?;
+    write(&definition);
+    if !reg.input_ast.is_empty() {
+        write(&parse(reg.input_ast)?);
+    } else if alias {
+        let alias = definition.iter().find_map(|item| match item {
+            Item::Decl(decl) => decl.input_alias(),
+            Item::Newline => None,
+        });
+        if let Some(alias) = alias {
+            write(&[Item::Decl(alias)]);
+        }
+    }
+    Ok(())
+}
+
+/// write `regs`, with an alias for each `Input` type referenced but not registered
+fn write_registrations(
+    regs: &[&TypeScriptifyRegistration],
+    format: &TsFormat,
+) -> io::Result<String> {
+    let aliased = regs
+        .iter()
+        .flat_map(|reg| reg.input_dependencies.iter().copied())
+        .collect::<BTreeSet<_>>();
+    let mut out = String::new();
+    for reg in regs {
+        write_registration(&mut out, reg, format, aliased.contains(reg.name))?;
+    }
+    Ok(out)
+}
+
+/// # The contents [`export_all`] would write, as a string.
+pub fn export_all_source() -> io::Result<String> {
+    export_all_source_with(&TsFormat::default())
+}
+
+/// # The contents [`export_all_with`] would write, as a string.
+pub fn export_all_source_with(format: &TsFormat) -> io::Result<String> {
+    write_registrations(&registered()?, format)
+}
+
+/// # Write the registered definitions reachable from `roots` to `path`.
+///
+/// Each root type is written along with every type it (transitively) references,
+/// dependencies before the types that use them.
+///
+/// Example:
+///
+/// ```ignore
+/// typescript_definitions::export_reachable(&["FrontendMessage"], "messages.ts")?;
+/// ```
+///
+/// Returns an error if a root or a dependency was never registered (i.e.
+/// it doesn't derive `TypeScriptify` or isn't linked into the binary).
+pub fn export_reachable<P: AsRef<Path>>(roots: &[&str], path: P) -> io::Result<()> {
+    std::fs::write(path, export_reachable_source(roots)?)
+}
+
+/// # [`export_reachable`] laid out with `format`.
+pub fn export_reachable_with<P: AsRef<Path>>(
+    roots: &[&str],
+    path: P,
+    format: &TsFormat,
+) -> io::Result<()> {
+    std::fs::write(path, export_reachable_source_with(roots, format)?)
+}
+
+/// # The contents [`export_reachable`] would write, as a string.
+pub fn export_reachable_source(roots: &[&str]) -> io::Result<String> {
+    export_reachable_source_with(roots, &TsFormat::default())
+}
+
+/// # The contents [`export_reachable_with`] would write, as a string.
+pub fn export_reachable_source_with(roots: &[&str], format: &TsFormat) -> io::Result<String> {
+    let types = registered()?
+        .into_iter()
+        .map(|reg| (reg.name, reg))
+        .collect::<BTreeMap<_, _>>();
+
+    fn visit<'a>(
+        name: &'a str,
+        used_by: Option<&str>,
+        types: &BTreeMap<&str, &'a TypeScriptifyRegistration>,
+        seen: &mut Vec<&'a str>,
+        order: &mut Vec<&'a TypeScriptifyRegistration>,
+    ) -> io::Result<()> {
+        if seen.contains(&name) {
+            // already exported (or a recursive type we are in the middle of)
+            return Ok(());
+        }
+        let reg = match types.get(name) {
+            Some(reg) => *reg,
+            None => {
+                let msg = match used_by {
+                    Some(parent) => format!(
+                        "typescript type \"{}\" (used by \"{}\") is not registered",
+                        name, parent
+                    ),
+                    None => format!("typescript type \"{}\" is not registered", name),
+                };
+                return Err(io::Error::new(io::ErrorKind::NotFound, msg));
+            }
+        };
+        seen.push(name);
+        for dep in reg.dependencies {
+            visit(dep, Some(name), types, seen, order)?;
+        }
+        order.push(reg);
+        Ok(())
+    }
+
+    let mut seen = vec![];
+    let mut order = vec![];
+    for root in roots {
+        visit(root, None, &types, &mut seen, &mut order)?;
+    }
+
+    write_registrations(&order, format)
+}
+
+/// # Write the JSON Schema of every registered type to `path`.
+///
+/// For [`SchemaDialect::JsonSchema`] this is a draft 2020-12 document with each
+/// type in `$defs`, for [`SchemaDialect::OpenApi`] an OpenAPI 3.1 fragment with
+/// each type in `components/schemas` (to merge into your API description).
+///
+/// Example:
+///
+/// ```ignore
+/// typescript_definitions::export_json_schema("schema.json", SchemaDialect::JsonSchema)?;
+/// ```
+pub fn export_json_schema<P: AsRef<Path>>(path: P, dialect: SchemaDialect) -> io::Result<()> {
+    std::fs::write(path, export_json_schema_source(dialect)?)
+}
+
+/// # The contents [`export_json_schema`] would write, as a string.
+pub fn export_json_schema_source(dialect: SchemaDialect) -> io::Result<String> {
+    use std::fmt::Write;
+    let (head, indent, tail) = match dialect {
+        SchemaDialect::JsonSchema => (
+            "{\n  \"$schema\": \"https://json-schema.org/draft/2020-12/schema\",\n  \"$defs\": {",
+            "    ",
+            "\n  }\n}\n",
+        ),
+        SchemaDialect::OpenApi => (
+            "{\n  \"components\": {\n    \"schemas\": {",
+            "      ",
+            "\n    }\n  }\n}\n",
+        ),
+    };
+    let mut out = String::from(head);
+    let mut sep = "";
+    for reg in registered()? {
+        let schema = match dialect {
+            SchemaDialect::JsonSchema => reg.json_schema,
+            SchemaDialect::OpenApi => reg.openapi_schema,
+        };
+        if schema.is_empty() {
+            // `JsonValue` has no schema of its own, it is inlined as `{}`
+            if reg.name == "JsonValue" {
+                continue;
+            }
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{} has no JSON schema: `json_schema = false` in the [outputs] of typescript-definitions.toml",
+                    reg.name
+                ),
+            ));
+        }
+        write!(&mut out, "{}\n{}\"{}\": {}", sep, indent, reg.name, schema).unwrap();
+        sep = ",";
+    }
+    out.push_str(tail);
+    Ok(out)
+}
+
 /// # String serializer for `u8` byte buffers.
 ///
 /// Use `#[serde(serialize_with="typescript_definitions::as_byte_string")]`
@@ -0,0 +1,353 @@
+// Copyright 2019 Ian Castleden
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! # Check that what serde writes matches the generated typescript.
+//!
+//! Example:
+//!
+//! ```ignore
+//! #[test]
+//! fn shapes_conform() {
+//!     typescript_definitions::testing::assert_conforms(&Shape::Circle { center, radius: 1.0 });
+//! }
+//! ```
+//!
+//! The value is serialized with `serde_json` and checked against the
+//! [`json_schema()`](crate::TypeScriptifyTrait::json_schema) of its type, which
+//! is made from the same parse as `type_script_ify()`. Other types are looked up in
+//! the registry (see [`registered`](crate::registered)) so they must be linked in.
+//!
+//! The check is structural, like typescript's: objects may have more properties
+//! than their type.
+//!
+//! The check fails if the crate's `typescript-definitions.toml` has
+//! `json_schema = false` in its `[outputs]`.
+
+use super::{registered, SchemaDialect, TypeScriptifyTrait};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// # Where a serialized value doesn't match its typescript type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    /// Where in the value, e.g. `$.shapes[1].radius`.
+    pub path: String,
+    /// What is wrong there, e.g. `expected number, found "1"`.
+    pub message: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+impl std::error::Error for Mismatch {}
+
+/// # Check the `serde_json` serialization of `value` against the typescript type of `T`.
+pub fn conforms<T: Serialize + TypeScriptifyTrait>(value: &T) -> Result<(), Mismatch> {
+    let mismatch = |message: String| Mismatch {
+        path: "$".into(),
+        message,
+    };
+    let value = serde_json::to_value(value).map_err(|e| mismatch(e.to_string()))?;
+    let checker = Checker::new().map_err(|e| mismatch(e.to_string()))?;
+    let schema = T::json_schema(SchemaDialect::JsonSchema).map_err(|e| {
+        mismatch(format!(
+            "{} has no JSON schema: {}",
+            std::any::type_name::<T>(),
+            e
+        ))
+    })?;
+    let schema = parse(&schema).map_err(mismatch)?;
+    checker.check(&schema, &value, "$")
+}
+
+/// # Panic if the `serde_json` serialization of `value` doesn't match the typescript type of `T`.
+///
+/// The panic message says where and prints the JSON.
+pub fn assert_conforms<T: Serialize + TypeScriptifyTrait>(value: &T) {
+    if let Err(mismatch) = conforms(value) {
+        let json = serde_json::to_string(value).unwrap_or_default();
+        panic!(
+            "{} doesn't match its typescript type at {}\n{}",
+            std::any::type_name::<T>(),
+            mismatch,
+            json
+        );
+    }
+}
+
+fn parse(schema: &str) -> Result<Value, String> {
+    serde_json::from_str(schema).map_err(|e| format!("bad schema {}: {}", schema, e))
+}
+
+fn no_schema(name: &str) -> String {
+    format!(
+        "{} has no JSON schema: `json_schema = false` in the [outputs] of typescript-definitions.toml",
+        name
+    )
+}
+
+/// `.key` or `["a key"]`
+fn key_path(path: &str, key: &str) -> String {
+    if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        format!("{}.{}", path, key)
+    } else {
+        format!("{}[{}]", path, Value::String(key.into()))
+    }
+}
+
+/// the only `pattern` we write: the keys of a map with `number` keys
+fn matches_pattern(pattern: &str, s: &str) -> bool {
+    match pattern {
+        "^-?[0-9]+$" => {
+            let digits = s.strip_prefix('-').unwrap_or(s);
+            !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+        }
+        _ => true,
+    }
+}
+
+fn is_type(name: &str, value: &Value) -> bool {
+    match name {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => false,
+    }
+}
+
+/// the schemas of the registered types
+struct Checker {
+    /// `None` for the types registered without a schema
+    defs: BTreeMap<&'static str, Option<Value>>,
+}
+
+impl Checker {
+    fn new() -> std::io::Result<Self> {
+        let mut defs = BTreeMap::new();
+        for reg in registered()? {
+            // `JsonValue` has no schema of its own (and is never referenced)
+            if reg.json_schema.is_empty() {
+                defs.insert(reg.name, None);
+                continue;
+            }
+            let schema = parse(reg.json_schema)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            defs.insert(reg.name, Some(schema));
+        }
+        Ok(Self { defs })
+    }
+
+    fn check(&self, schema: &Value, value: &Value, path: &str) -> Result<(), Mismatch> {
+        let mismatch = |message: String| {
+            Err(Mismatch {
+                path: path.into(),
+                message,
+            })
+        };
+        let schema = match schema {
+            Value::Bool(true) => return Ok(()),
+            Value::Object(schema) => schema,
+            _ => return mismatch(format!("no value is allowed here, found {}", value)),
+        };
+
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            let name = reference.trim_start_matches("#/$defs/");
+            match self.defs.get(name) {
+                Some(Some(def)) => self.check(def, value, path)?,
+                Some(None) => return mismatch(no_schema(name)),
+                None => return mismatch(format!("type \"{}\" is not registered", name)),
+            }
+        }
+        if let Some(ty) = schema.get("type").and_then(Value::as_str) {
+            if !is_type(ty, value) {
+                return mismatch(format!("expected {}, found {}", ty, value));
+            }
+        }
+        if let Some(expected) = schema.get("const") {
+            if expected != value {
+                return mismatch(format!("expected {}, found {}", expected, value));
+            }
+        }
+        if let Some(Value::Array(values)) = schema.get("enum") {
+            if !values.contains(value) {
+                let values = values.iter().map(Value::to_string).collect::<Vec<_>>();
+                return mismatch(format!(
+                    "expected one of {}, found {}",
+                    values.join(", "),
+                    value
+                ));
+            }
+        }
+        if let Some(not) = schema.get("not") {
+            if self.check(not, value, path).is_ok() {
+                return mismatch(format!("no value is allowed here, found {}", value));
+            }
+        }
+        if let Some(Value::Array(schemas)) = schema.get("allOf") {
+            for schema in schemas {
+                self.check(schema, value, path)?;
+            }
+        }
+        if let Some(Value::Array(schemas)) = schema.get("anyOf") {
+            self.check_union(schemas, value, path, false)?;
+        }
+        if let Some(Value::Array(schemas)) = schema.get("oneOf") {
+            self.check_union(schemas, value, path, true)?;
+        }
+        if let Value::Number(n) = value {
+            let n = n.as_f64().unwrap_or_default();
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+                if n < min {
+                    return mismatch(format!("{} is less than {}", n, min));
+                }
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+                if n > max {
+                    return mismatch(format!("{} is more than {}", n, max));
+                }
+            }
+        }
+        if let Value::Array(elems) = value {
+            self.check_array(schema, elems, path)?;
+        }
+        if let Value::Object(props) = value {
+            self.check_object(schema, props, path)?;
+        }
+        Ok(())
+    }
+
+    /// `oneOf` (an enum's variants) must match exactly one, `anyOf` at least one
+    fn check_union(
+        &self,
+        schemas: &[Value],
+        value: &Value,
+        path: &str,
+        exclusive: bool,
+    ) -> Result<(), Mismatch> {
+        let mut errors = vec![];
+        for schema in schemas {
+            if let Err(err) = self.check(schema, value, path) {
+                errors.push(err);
+            }
+        }
+        let matched = schemas.len() - errors.len();
+        if exclusive && matched > 1 {
+            return Err(Mismatch {
+                path: path.into(),
+                message: format!("{} matches {} variants", value, matched),
+            });
+        }
+        if matched > 0 {
+            return Ok(());
+        }
+        // the option that got furthest is most likely the one that was meant
+        match errors.into_iter().max_by_key(|err| err.path.len()) {
+            Some(err) if err.path != path => Err(err),
+            _ => Err(Mismatch {
+                path: path.into(),
+                message: format!("{} matches none of the {} options", value, schemas.len()),
+            }),
+        }
+    }
+
+    fn check_array(
+        &self,
+        schema: &serde_json::Map<String, Value>,
+        elems: &[Value],
+        path: &str,
+    ) -> Result<(), Mismatch> {
+        let prefix = match schema.get("prefixItems") {
+            Some(Value::Array(prefix)) => &prefix[..],
+            _ => &[],
+        };
+        if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+            if (elems.len() as u64) < min {
+                return Err(Mismatch {
+                    path: path.into(),
+                    message: format!("expected {} elements, found {}", min, elems.len()),
+                });
+            }
+        }
+        for (i, elem) in elems.iter().enumerate() {
+            let elem_path = format!("{}[{}]", path, i);
+            match prefix.get(i) {
+                Some(schema) => self.check(schema, elem, &elem_path)?,
+                None => match schema.get("items") {
+                    Some(Value::Bool(false)) => {
+                        return Err(Mismatch {
+                            path: path.into(),
+                            message: format!(
+                                "expected {} elements, found {}",
+                                prefix.len(),
+                                elems.len()
+                            ),
+                        })
+                    }
+                    Some(items) => self.check(items, elem, &elem_path)?,
+                    None => {}
+                },
+            }
+        }
+        Ok(())
+    }
+
+    fn check_object(
+        &self,
+        schema: &serde_json::Map<String, Value>,
+        props: &serde_json::Map<String, Value>,
+        path: &str,
+    ) -> Result<(), Mismatch> {
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !props.contains_key(key) {
+                    return Err(Mismatch {
+                        path: path.into(),
+                        message: format!("missing property \"{}\"", key),
+                    });
+                }
+            }
+        }
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for (key, prop) in props {
+            let prop_path = key_path(path, key);
+            match properties.and_then(|p| p.get(key)) {
+                Some(schema) => self.check(schema, prop, &prop_path)?,
+                None => {
+                    if let Some(names) = schema.get("propertyNames") {
+                        self.check_key(names, key, &prop_path)?;
+                    }
+                    if let Some(additional) = schema.get("additionalProperties") {
+                        self.check(additional, prop, &prop_path)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// a map's key against its `propertyNames`
+    fn check_key(&self, schema: &Value, key: &str, path: &str) -> Result<(), Mismatch> {
+        match schema.get("pattern").and_then(Value::as_str) {
+            Some(pattern) if !matches_pattern(pattern, key) => Err(Mismatch {
+                path: path.into(),
+                message: format!("key \"{}\" isn't a number", key),
+            }),
+            Some(..) => Ok(()),
+            None => self.check(schema, &Value::String(key.into()), path),
+        }
+    }
+}
@@ -0,0 +1,149 @@
+#![allow(dead_code)]
+use serde::Serialize;
+use typescript_definitions::{
+    export_reachable_source, export_reachable_source_with, Quote, Separator, TsFormat,
+    TypeScriptify, TypeScriptifyTrait,
+};
+use typescript_definitions_ast::{Decl, Type};
+
+#[derive(Serialize, TypeScriptify)]
+struct Point {
+    /// across
+    x: i32,
+    y: i32,
+}
+
+#[derive(Serialize, TypeScriptify)]
+#[serde(tag = "kind")]
+enum Shape {
+    Circle {
+        center: Point,
+        radius: f64,
+    },
+    Line {
+        start: Point,
+        end: Point,
+    },
+    #[serde(rename = "it's empty")]
+    Empty,
+}
+
+#[derive(Serialize, TypeScriptify)]
+#[serde(rename_all = "kebab-case")]
+enum Direction {
+    /// up and right
+    NorthEast,
+    SouthWest,
+}
+
+fn export_with(root: &str, format: &TsFormat) -> String {
+    export_reachable_source_with(&[root], format).unwrap()
+}
+
+fn decl(name: &str, ty: &str) -> Decl {
+    Decl::Type {
+        docs: vec![],
+        export: true,
+        name: name.into(),
+        type_params: vec![],
+        ty: Type::parse(ty).unwrap(),
+    }
+}
+
+#[test]
+fn default_is_the_generated_layout() {
+    let format = TsFormat::default();
+    assert_eq!(
+        export_with("Shape", &format),
+        format!(
+            "{}\n{}\n",
+            Point::type_script_ify(),
+            Shape::type_script_ify()
+        )
+    );
+    assert_eq!(
+        export_with("Direction", &format),
+        format!("{}\n", Direction::type_script_ify())
+    );
+    assert_eq!(
+        export_with("Shape", &format),
+        export_reachable_source(&["Shape"]).unwrap()
+    );
+}
+
+#[test]
+fn indent_and_separator() {
+    let format = TsFormat {
+        indent: 2,
+        separator: Separator::Comma,
+        ..TsFormat::default()
+    };
+    let source = export_with("Shape", &format);
+    assert!(source.starts_with(
+        "export type Point = {\n  /**\n   * across\n   */\n  x: number,\n  y: number,\n}\n"
+    ));
+    assert!(source.contains(
+        "export type Shape =\n  | { kind: \"Circle\", center: Point, radius: number }\n  | { kind: \"Line\", start: Point, end: Point }\n  | { kind: \"it's empty\" }\n"
+    ));
+}
+
+#[test]
+fn single_quotes() {
+    let format = TsFormat {
+        quote: Quote::Single,
+        ..TsFormat::default()
+    };
+    assert!(export_with("Direction", &format).starts_with(
+        "export enum Direction {\n    /**\n     * up and right\n     */\n    'north-east' = 'north-east',\n    'south-west' = 'south-west',\n}\n"
+    ));
+    assert!(export_with("Shape", &format).contains("| { kind: 'it\\'s empty' }\n"));
+}
+
+#[test]
+fn trailing_commas() {
+    let format = TsFormat {
+        trailing_comma: false,
+        ..TsFormat::default()
+    };
+    assert!(export_with("Direction", &format).contains("    \"south-west\" = \"south-west\"\n}\n"));
+    // `;` is kept
+    assert!(export_with("Point", &format).contains("    y: number;\n}\n"));
+
+    let format = TsFormat {
+        trailing_comma: false,
+        separator: Separator::Comma,
+        ..TsFormat::default()
+    };
+    assert!(export_with("Point", &format).contains("    x: number,\n    y: number\n}\n"));
+}
+
+#[test]
+fn wrap_long_lines() {
+    let format = TsFormat {
+        line_width: Some(30),
+        ..TsFormat::default()
+    };
+    assert_eq!(
+        decl("Level", "\"low\" | \"medium\" | \"high\" | \"extreme\"").print(&format),
+        "export type Level =\n    | \"low\"\n    | \"medium\"\n    | \"high\"\n    | \"extreme\""
+    );
+    assert_eq!(
+        decl("S", "{ level: \"low\" | \"medium\" | \"high\"; n: number }").print(&format),
+        "export type S = {\n    level:\n        | \"low\"\n        | \"medium\"\n        | \"high\";\n    n: number;\n}"
+    );
+    // short lines are left alone
+    assert_eq!(
+        decl("L", "\"low\" | \"high\"").print(&format),
+        "export type L = \"low\" | \"high\""
+    );
+}
+
+#[test]
+fn export_with_format() {
+    let format = TsFormat {
+        indent: 2,
+        ..TsFormat::default()
+    };
+    let source = typescript_definitions::export_all_source_with(&format).unwrap();
+    assert!(source.contains("export type Point = {\n  /**\n   * across\n   */\n  x: number;\n"));
+}
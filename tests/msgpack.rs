@@ -0,0 +1,25 @@
+use serde::Serialize;
+use typescript_definitions::{TypeScriptify, TypeScriptifyTrait};
+
+#[derive(Serialize, TypeScriptify)]
+#[ts(profile = "msgpack")]
+struct Unit;
+
+#[test]
+fn unit_struct_is_an_empty_array() {
+    for bytes in [
+        rmp_serde::to_vec(&Unit).unwrap(),
+        rmp_serde::to_vec_named(&Unit).unwrap(),
+    ] {
+        let value: serde_json::Value = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(value, serde_json::json!([]));
+    }
+    assert_eq!(
+        Unit::type_script_ify(),
+        "export type Unit = []\nexport const Unit = (check: Unit) => check\n"
+    );
+    assert_eq!(
+        Unit::type_script_guard().unwrap(),
+        "export function isUnit(obj: any): obj is Unit {\n    return Array.isArray(obj) && obj.length === 0;\n}"
+    );
+}
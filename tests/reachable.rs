@@ -0,0 +1,128 @@
+#![allow(dead_code)]
+use serde::Serialize;
+use typescript_definitions::{TypeScriptify, TypeScriptifyTrait};
+
+#[derive(Serialize, TypeScriptify)]
+struct Value<T> {
+    value: T,
+}
+
+#[derive(Serialize, TypeScriptify)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Serialize, TypeScriptify)]
+#[serde(tag = "tag", content = "fields")]
+enum FrontendMessage {
+    Init { id: String },
+    Stuff { borrow: Value<i32>, at: Vec<Point> },
+    Nested(Box<FrontendMessage>),
+}
+
+#[derive(Serialize, TypeScriptify)]
+struct Unrelated {
+    a: i32,
+}
+
+#[derive(Serialize, TypeScriptify)]
+struct Dangling {
+    missing: Missing,
+}
+
+#[derive(Serialize)]
+struct Missing;
+
+#[derive(Serialize, TypeScriptify)]
+struct Event {
+    payload: serde_json::Value,
+    borrow: Value<i32>,
+}
+
+#[derive(Serialize, TypeScriptify)]
+struct Filter {
+    name: String,
+}
+
+#[derive(Serialize, TypeScriptify)]
+#[ts(input)]
+struct Query {
+    filter: Filter,
+    page: Value<u32>,
+}
+
+#[test]
+fn dependencies_are_listed() {
+    assert_eq!(FrontendMessage::dependencies(), ["Value", "Point"]);
+    assert!(Value::<i32>::dependencies().is_empty());
+}
+
+#[test]
+fn reachable_in_dependency_order() {
+    let source = typescript_definitions::export_reachable_source(&["FrontendMessage"]).unwrap();
+    assert_eq!(
+        source,
+        format!(
+            "{}\n{}\n{}\n",
+            Value::<i32>::type_script_ify(),
+            Point::type_script_ify(),
+            FrontendMessage::type_script_ify()
+        )
+    );
+}
+
+#[test]
+fn reachable_reports_missing() {
+    let err =
+        typescript_definitions::export_reachable_source(&["Unrelated", "Dangling"]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "typescript type \"Missing\" (used by \"Dangling\") is not registered"
+    );
+}
+
+#[test]
+fn json_value_is_exported() {
+    assert_eq!(Event::dependencies(), ["Value", "JsonValue"]);
+    let source = typescript_definitions::export_reachable_source(&["Event"]).unwrap();
+    assert!(source.contains(
+        "export type JsonValue = null | boolean | number | string | JsonValue[] | { [key: string]: JsonValue }\n"
+    ));
+    assert!(Event::type_script_ify().contains("payload: JsonValue;"));
+    assert!(source.contains(&*Event::type_script_ify()));
+}
+
+#[test]
+fn no_blank_line_without_input() {
+    let source = typescript_definitions::export_reachable_source(&["Event"]).unwrap();
+    assert_eq!(
+        source,
+        format!(
+            "{}\n{}\n{}\n",
+            Value::<i32>::type_script_ify(),
+            "export type JsonValue = null | boolean | number | string | JsonValue[] | { [key: string]: JsonValue }",
+            Event::type_script_ify()
+        )
+    );
+}
+
+#[test]
+fn input_references_are_aliased() {
+    let source = typescript_definitions::export_reachable_source(&["Query"]).unwrap();
+    assert_eq!(
+        source,
+        format!(
+            "{}\n{}\n{}\n{}\n{}\n{}\n",
+            Filter::type_script_ify(),
+            "export type FilterInput = Filter",
+            Value::<i32>::type_script_ify(),
+            "export type ValueInput<T> = Value<T>",
+            Query::type_script_ify(),
+            "export type QueryInput = { filter: FilterInput; page: ValueInput<number> }",
+        )
+    );
+    // only written when an `Input` type references them
+    let source = typescript_definitions::export_reachable_source(&["Event"]).unwrap();
+    assert!(!source.contains("ValueInput"));
+}
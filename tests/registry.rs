@@ -0,0 +1,115 @@
+#![allow(dead_code)]
+use serde::Serialize;
+use typescript_definitions::{SchemaDialect, TypeScriptify, TypeScriptifyTrait};
+
+#[derive(Serialize, TypeScriptify)]
+#[ts(input)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Serialize, TypeScriptify)]
+#[ts(input)]
+struct Line {
+    start: Point,
+    end: Point,
+}
+
+#[derive(Serialize, TypeScriptify)]
+struct Labelled<T> {
+    label: String,
+    value: T,
+}
+
+#[derive(Serialize, TypeScriptify)]
+enum Color {
+    Red,
+    Green,
+}
+
+#[test]
+fn registry_is_sorted() {
+    let names = typescript_definitions::registered()
+        .unwrap()
+        .into_iter()
+        .map(|r| r.name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["Color", "Labelled", "Line", "Point"]);
+}
+
+#[test]
+fn export_all_writes_every_type() {
+    let path = std::env::temp_dir().join(format!("tsy-export-all-{}.ts", std::process::id()));
+    typescript_definitions::export_all(&path).unwrap();
+    let source = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        source,
+        format!(
+            "{}\n{}\n{}\n{}\n{}\n{}\n",
+            Color::type_script_ify(),
+            Labelled::<()>::type_script_ify(),
+            Line::type_script_ify(),
+            Line::type_script_input(),
+            Point::type_script_ify(),
+            Point::type_script_input()
+        )
+    );
+    // declared once: a second `enum Color` would be a duplicate identifier
+    assert_eq!(source.matches("export enum Color").count(), 1);
+}
+
+#[test]
+fn input_shapes() {
+    assert_eq!(Labelled::<()>::type_script_input(), "");
+    assert_eq!(
+        Point::type_script_input(),
+        "export type PointInput = { x: number; y: number }"
+    );
+    assert_eq!(
+        Line::type_script_input(),
+        "export type LineInput = { start: PointInput; end: PointInput }"
+    );
+}
+
+#[test]
+fn json_schema_documents() {
+    assert_eq!(
+        Line::json_schema(SchemaDialect::JsonSchema).unwrap(),
+        r##"{"properties":{"end":{"$ref":"#/$defs/Point"},"start":{"$ref":"#/$defs/Point"}},"required":["start","end"],"title":"Line","type":"object"}"##
+    );
+    let source =
+        typescript_definitions::export_json_schema_source(SchemaDialect::JsonSchema).unwrap();
+    assert_eq!(
+        source,
+        format!(
+            "{{\n  \"$schema\": \"https://json-schema.org/draft/2020-12/schema\",\n  \"$defs\": {{\n    \"Color\": {},\n    \"Labelled\": {},\n    \"Line\": {},\n    \"Point\": {}\n  }}\n}}\n",
+            Color::json_schema(SchemaDialect::JsonSchema).unwrap(),
+            Labelled::<()>::json_schema(SchemaDialect::JsonSchema).unwrap(),
+            Line::json_schema(SchemaDialect::JsonSchema).unwrap(),
+            Point::json_schema(SchemaDialect::JsonSchema).unwrap()
+        )
+    );
+    let source = typescript_definitions::export_json_schema_source(SchemaDialect::OpenApi).unwrap();
+    assert!(source.starts_with("{\n  \"components\": {\n    \"schemas\": {\n      \"Color\": "));
+    assert!(source.contains("{\"$ref\":\"#/components/schemas/Point\"}"));
+}
+
+struct Manual;
+
+impl TypeScriptifyTrait for Manual {
+    fn type_script_ify() -> std::borrow::Cow<'static, str> {
+        "export type Manual = string".into()
+    }
+}
+
+#[test]
+fn trait_defaults_are_empty() {
+    assert!(Manual::type_script_guard().is_err());
+    assert!(Manual::type_script_zod().is_err());
+    assert!(Manual::json_schema(SchemaDialect::OpenApi).is_err());
+    assert_eq!(Manual::type_script_input(), "");
+    assert!(Manual::dependencies().is_empty());
+}
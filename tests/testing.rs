@@ -0,0 +1,105 @@
+#![allow(dead_code)]
+use serde::Serialize;
+use std::collections::HashMap;
+use typescript_definitions::testing::{assert_conforms, conforms, Mismatch};
+use typescript_definitions::TypeScriptify;
+
+#[derive(Serialize, TypeScriptify)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Serialize, TypeScriptify)]
+#[serde(tag = "kind")]
+enum Shape {
+    Circle { center: Point, radius: f64 },
+    // serde writes the point's fields next to the tag
+    Dot(Point),
+    Empty,
+}
+
+#[derive(Serialize, TypeScriptify)]
+enum External {
+    A(i32),
+    B { x: u8 },
+}
+
+#[derive(Serialize, TypeScriptify)]
+#[serde(untagged)]
+enum Untagged {
+    A(i32),
+    B { x: u8 },
+}
+
+#[derive(Serialize, TypeScriptify)]
+struct Unit;
+
+#[derive(Serialize, TypeScriptify)]
+struct Drawing {
+    shapes: Vec<Shape>,
+    layers: HashMap<u32, String>,
+    origin: Option<(i32, i32)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    marker: Unit,
+}
+
+#[derive(Serialize, TypeScriptify)]
+struct Wrong {
+    #[ts(ts_type = "number")]
+    id: String,
+}
+
+#[test]
+fn nested_values_conform() {
+    assert_conforms(&Drawing {
+        shapes: vec![
+            Shape::Circle {
+                center: Point { x: 0, y: 1 },
+                radius: 2.5,
+            },
+            Shape::Dot(Point { x: 2, y: 3 }),
+            Shape::Empty,
+        ],
+        layers: vec![(1, "base".to_string())].into_iter().collect(),
+        origin: None,
+        title: None,
+        marker: Unit,
+    });
+    assert_conforms(&Unit);
+}
+
+#[test]
+fn untagged_unions_derive() {
+    use typescript_definitions::TypeScriptifyTrait;
+    assert_eq!(
+        External::type_script_ify(),
+        "export type External =\n    | { A: number }\n    | { B: { x: number } }\n"
+    );
+    assert_eq!(
+        Untagged::type_script_ify(),
+        "export type Untagged =\n    | number\n    | { x: number }\n"
+    );
+    assert_conforms(&External::A(1));
+    assert_conforms(&External::B { x: 2 });
+    assert_conforms(&Untagged::A(1));
+    assert_conforms(&Untagged::B { x: 2 });
+}
+
+#[test]
+fn mismatches_say_where() {
+    assert_eq!(
+        conforms(&Wrong { id: "a".into() }),
+        Err(Mismatch {
+            path: "$.id".into(),
+            message: "expected number, found \"a\"".into(),
+        })
+    );
+}
+
+#[test]
+#[should_panic(expected = "doesn't match its typescript type at $.id")]
+fn assert_conforms_panics() {
+    assert_conforms(&Wrong { id: "a".into() });
+}
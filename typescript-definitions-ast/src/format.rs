@@ -0,0 +1,85 @@
+// Copyright 2019 Ian Castleden
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! # Format
+//!
+//! The layout options of the printer in `ts.rs`. The derive macros print with
+//! the default; the `_with` export functions print the registered syntax trees
+//! again with the options asked for.
+
+/// What goes between the members of an object type or interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Separator {
+    /// `{ a: A; b: B }`
+    Semicolon,
+    /// `{ a: A, b: B }`
+    Comma,
+}
+
+/// The quote character used for string literals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quote {
+    Double,
+    Single,
+}
+
+/// # Layout options for generated typescript.
+///
+/// The default is the layout the derive macros print.
+///
+/// Example:
+///
+/// ```
+/// use typescript_definitions_ast::{Decl, Quote, Separator, TsFormat, Type};
+///
+/// let point = Decl::Type {
+///     docs: vec![],
+///     export: true,
+///     name: "Point".into(),
+///     type_params: vec![],
+///     ty: Type::parse("{ kind: \"point\"; x: number }").unwrap(),
+/// };
+/// let format = TsFormat {
+///     indent: 2,
+///     separator: Separator::Comma,
+///     quote: Quote::Single,
+///     ..TsFormat::default()
+/// };
+/// assert_eq!(
+///     point.print(&format),
+///     "export type Point = { kind: 'point', x: number }"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TsFormat {
+    /// Spaces per indentation level.
+    pub indent: usize,
+    /// The separator between the members of object types and interfaces.
+    pub separator: Separator,
+    /// The quote used for string literals (doc comments are left alone).
+    pub quote: Quote,
+    /// Put a `,` after the last item of an object, enum (or, with
+    /// [`Separator::Comma`], object type) that is laid out one item per line.
+    pub trailing_comma: bool,
+    /// Break lines longer than this: a union becomes one `| variant` per line
+    /// and an object (type) or enum one member per line. Other lines (e.g. long
+    /// type guard expressions) are left as they are.
+    pub line_width: Option<usize>,
+}
+
+impl Default for TsFormat {
+    fn default() -> TsFormat {
+        TsFormat {
+            indent: 4,
+            separator: Separator::Semicolon,
+            quote: Quote::Double,
+            trailing_comma: true,
+            line_width: None,
+        }
+    }
+}
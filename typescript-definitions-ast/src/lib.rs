@@ -0,0 +1,22 @@
+// Copyright 2019 Ian Castleden
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The typescript syntax tree built by the `typescript-definitions` derive macros
+//! and its printer.
+//!
+//! The derive macros register the (serialized) tree of each type, so the
+//! `_with` export functions of
+//! [typescript-definitions](https://crates.io/crates/typescript-definitions)
+//! can print it again with a [`TsFormat`]. This is an implementation detail:
+//! use that crate.
+
+mod format;
+mod ts;
+
+pub use format::{Quote, Separator, TsFormat};
+pub use ts::*;
@@ -0,0 +1,1397 @@
+// Copyright 2019 Ian Castleden
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! # Ts
+//!
+//! A syntax tree for the typescript we generate, and its printer.
+//! `tots.rs`, `guard.rs`, `derive_struct.rs` and `derive_enum.rs` (in
+//! typescript-definitions-core) build these nodes and `print` renders them
+//! with a [`TsFormat`] (`Display` with the default): parentheses come from
+//! operator precedence and object keys that aren't identifiers are quoted,
+//! so the output is valid typescript however we got there.
+
+use super::format::{Quote, Separator, TsFormat};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// the lines of a `/** ... */` comment
+pub type Docs = Vec<String>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Lit {
+    Str(String),
+    /// the source of a number: `1`, `-1`, `1.5`
+    Num(String),
+    Bool(bool),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Type {
+    /// `number` or `Foo<A, B>`
+    Ref(String, Vec<Type>),
+    /// `"a"`, `1`, `true`
+    Lit(Lit),
+    /// `T[]`
+    Array(Box<Type>),
+    /// `[A, B]`
+    Tuple(Vec<Type>),
+    /// `{ a: A; b?: B }`
+    Object(Vec<Member>),
+    /// `A | B`
+    Union(Vec<Type>),
+    /// `A & B`
+    Intersection(Vec<Type>),
+    /// `(a: A) => R`
+    Function(Vec<Param>, Box<Type>),
+    /// `obj is T` (a type guard's return type)
+    Predicate(String, Box<Type>),
+    /// `typeof Foo`
+    Typeof(String),
+    /// `keyof T`
+    Keyof(Box<Type>),
+    /// `readonly T[]` (only arrays and tuples)
+    Readonly(Box<Type>),
+    /// `T[K]`
+    IndexedAccess(Box<Type>, Box<Type>),
+    /// typescript we were given but don't understand, printed as is
+    Raw(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Member {
+    /// `readonly key?: T`
+    Property {
+        docs: Docs,
+        key: String,
+        optional: bool,
+        readonly: bool,
+        ty: Type,
+    },
+    /// `[key: K]: V`
+    Index { key: Type, value: Type },
+    /// `name<T>(a: A): R`
+    Method {
+        name: String,
+        type_params: Vec<TypeParam>,
+        params: Vec<Param>,
+        ret: Type,
+    },
+}
+
+/// a function parameter: `name: T`. `name` may be a pattern `[k, v]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Param {
+    pub name: String,
+    pub ty: Option<Type>,
+}
+
+/// `T extends Bound = Default`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TypeParam {
+    pub name: String,
+    pub bound: Option<Type>,
+    pub default: Option<Type>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BinOp {
+    Eq3,
+    NotEq3,
+    In,
+    InstanceOf,
+    Add,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Expr {
+    Ident(String),
+    Lit(Lit),
+    /// `obj.name` (or `obj["name"]`)
+    Member(Box<Expr>, String),
+    /// `obj[index]`
+    Index(Box<Expr>, Box<Expr>),
+    Call(Box<Expr>, Vec<Expr>),
+    /// `f<T>` (before a call)
+    TypeArgs(Box<Expr>, Vec<Type>),
+    /// `<T>(a: A): R => body`
+    Arrow(Box<Function>),
+    Not(Box<Expr>),
+    Typeof(Box<Expr>),
+    /// `+a`
+    Plus(Box<Expr>),
+    Binary(Box<Expr>, BinOp, Box<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Array(Vec<Expr>),
+    Object(Vec<Prop>),
+    /// `e as const`
+    AsConst(Box<Expr>),
+}
+
+/// a member of an object literal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Prop {
+    /// `key: value`
+    Value {
+        docs: Docs,
+        key: String,
+        value: Expr,
+    },
+    /// `...value`
+    Spread(Expr),
+    /// `name(a: A): R { ... }`
+    Method { name: String, function: Function },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Function {
+    pub type_params: Vec<TypeParam>,
+    pub params: Vec<Param>,
+    pub ret: Option<Type>,
+    pub body: Body,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Body {
+    Expr(Expr),
+    Block(Vec<Stmt>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Stmt {
+    Return(Expr),
+    /// `//text`
+    Comment(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnumMember {
+    pub docs: Docs,
+    pub name: String,
+    pub value: Lit,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Decl {
+    /// `type Foo<T> = T`
+    Type {
+        docs: Docs,
+        export: bool,
+        name: String,
+        type_params: Vec<TypeParam>,
+        ty: Type,
+    },
+    /// `enum Foo { A = "A" }` or a `const enum`
+    Enum {
+        docs: Docs,
+        is_const: bool,
+        name: String,
+        members: Vec<EnumMember>,
+    },
+    Interface {
+        docs: Docs,
+        name: String,
+        type_params: Vec<TypeParam>,
+        members: Vec<Member>,
+    },
+    /// `const Foo: T = value`
+    Const {
+        docs: Docs,
+        name: String,
+        ty: Option<Type>,
+        value: Expr,
+    },
+    Function {
+        docs: Docs,
+        name: String,
+        function: Function,
+    },
+    Namespace {
+        name: String,
+        decls: Vec<Decl>,
+    },
+}
+
+/// A piece of the generated typescript: a declaration or a line break.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Item {
+    Decl(Decl),
+    Newline,
+}
+
+/// print `items` (e.g. the output of `type_script_ify()`) with `format`
+pub fn print(items: &[Item], format: &TsFormat) -> String {
+    let mut p = Printer::new(format);
+    for item in items {
+        match item {
+            Item::Decl(decl) => p.decl(decl),
+            Item::Newline => p.push("\n"),
+        }
+    }
+    p.out
+}
+
+/// javascript/typescript reserved words. They are legal object keys but
+/// not names, so we quote them as keys too.
+const RESERVED: &[&str] = &[
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+];
+
+/// can `s` be written without quotes as an object key or after a `.`,
+/// and be used as a name?
+pub fn is_ident(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+        && !RESERVED.contains(&s)
+}
+
+impl Type {
+    pub fn named<S: Into<String>>(name: S) -> Type {
+        Type::Ref(name.into(), vec![])
+    }
+
+    pub fn str<S: Into<String>>(s: S) -> Type {
+        Type::Lit(Lit::Str(s.into()))
+    }
+
+    /// `A | B`: `never` if there is nothing, nested unions are flattened
+    pub fn union(types: Vec<Type>) -> Type {
+        let mut v = vec![];
+        for ty in types {
+            match ty {
+                Type::Union(inner) => v.extend(inner),
+                ty => v.push(ty),
+            }
+        }
+        match v.len() {
+            0 => Type::named("never"),
+            1 => v.remove(0),
+            _ => Type::Union(v),
+        }
+    }
+
+    /// `A & B`: `unknown` if there is nothing
+    pub fn intersection(types: Vec<Type>) -> Type {
+        let mut v = vec![];
+        for ty in types {
+            match ty {
+                Type::Intersection(inner) => v.extend(inner),
+                ty => v.push(ty),
+            }
+        }
+        match v.len() {
+            0 => Type::named("unknown"),
+            1 => v.remove(0),
+            _ => Type::Intersection(v),
+        }
+    }
+
+    pub fn array(elem: Type) -> Type {
+        Type::Array(Box::new(elem))
+    }
+
+    /// typescript from the user (`ts_type`, `typescript-definitions.toml`): if we
+    /// can parse it we print it like our own, otherwise exactly as given
+    pub fn from_source(s: &str) -> Type {
+        Type::parse(s).unwrap_or_else(|_| Type::Raw(s.trim().to_string()))
+    }
+
+    /// parse the subset of typescript types we also generate
+    pub fn parse(s: &str) -> Result<Type, String> {
+        TypeParser::parse(s)
+    }
+
+    fn prec(&self) -> u8 {
+        match self {
+            Type::Function(..) | Type::Predicate(..) | Type::Raw(..) => 0,
+            Type::Union(v) | Type::Intersection(v) if v.len() == 1 => v[0].prec(),
+            Type::Union(..) => 1,
+            Type::Intersection(..) => 2,
+            Type::Typeof(..) | Type::Keyof(..) | Type::Readonly(..) => 3,
+            Type::Array(..) | Type::IndexedAccess(..) => 4,
+            _ => 5,
+        }
+    }
+}
+
+impl Param {
+    pub fn new<S: Into<String>>(name: S, ty: Type) -> Param {
+        Param {
+            name: name.into(),
+            ty: Some(ty),
+        }
+    }
+
+    /// an untyped parameter (for an arrow function whose type is inferred)
+    pub fn untyped<S: Into<String>>(name: S) -> Param {
+        Param {
+            name: name.into(),
+            ty: None,
+        }
+    }
+}
+
+impl TypeParam {
+    pub fn new<S: Into<String>>(name: S) -> TypeParam {
+        TypeParam {
+            name: name.into(),
+            bound: None,
+            default: None,
+        }
+    }
+}
+
+impl Expr {
+    pub fn ident<S: Into<String>>(s: S) -> Expr {
+        Expr::Ident(s.into())
+    }
+
+    pub fn str<S: Into<String>>(s: S) -> Expr {
+        Expr::Lit(Lit::Str(s.into()))
+    }
+
+    pub fn num(n: usize) -> Expr {
+        Expr::Lit(Lit::Num(n.to_string()))
+    }
+
+    pub fn member<S: Into<String>>(&self, name: S) -> Expr {
+        Expr::Member(Box::new(self.clone()), name.into())
+    }
+
+    pub fn index(&self, index: Expr) -> Expr {
+        Expr::Index(Box::new(self.clone()), Box::new(index))
+    }
+
+    pub fn call(self, args: Vec<Expr>) -> Expr {
+        Expr::Call(Box::new(self), args)
+    }
+
+    /// `self.name(args)`
+    pub fn method<S: Into<String>>(&self, name: S, args: Vec<Expr>) -> Expr {
+        self.member(name).call(args)
+    }
+
+    pub fn eq3(&self, other: Expr) -> Expr {
+        Expr::Binary(Box::new(self.clone()), BinOp::Eq3, Box::new(other))
+    }
+
+    pub fn not_eq3(&self, other: Expr) -> Expr {
+        Expr::Binary(Box::new(self.clone()), BinOp::NotEq3, Box::new(other))
+    }
+
+    /// `typeof self === "name"`
+    pub fn typeof_is(&self, name: &str) -> Expr {
+        Expr::Typeof(Box::new(self.clone())).eq3(Expr::str(name))
+    }
+
+    /// `"key" in self`
+    pub fn has(&self, key: &str) -> Expr {
+        Expr::Binary(Box::new(Expr::str(key)), BinOp::In, Box::new(self.clone()))
+    }
+
+    /// `self instanceof class`
+    pub fn instance_of(&self, class: &str) -> Expr {
+        Expr::Binary(
+            Box::new(self.clone()),
+            BinOp::InstanceOf,
+            Box::new(Expr::ident(class)),
+        )
+    }
+
+    pub fn arrow(params: Vec<Param>, ret: Option<Type>, body: Expr) -> Expr {
+        Expr::Arrow(Box::new(Function {
+            type_params: vec![],
+            params,
+            ret,
+            body: Body::Expr(body),
+        }))
+    }
+
+    /// `a && b`: `true` if there is nothing
+    pub fn and(exprs: Vec<Expr>) -> Expr {
+        let mut v = vec![];
+        for e in exprs {
+            match e {
+                Expr::And(inner) => v.extend(inner),
+                // an unchecked part
+                Expr::Lit(Lit::Bool(true)) => {}
+                e => v.push(e),
+            }
+        }
+        match v.len() {
+            0 => Expr::Lit(Lit::Bool(true)),
+            1 => v.remove(0),
+            _ => Expr::And(v),
+        }
+    }
+
+    /// `a || b`: `false` if there is nothing
+    pub fn or(exprs: Vec<Expr>) -> Expr {
+        let mut v = vec![];
+        for e in exprs {
+            match e {
+                Expr::Or(inner) => v.extend(inner),
+                e => v.push(e),
+            }
+        }
+        match v.len() {
+            0 => Expr::Lit(Lit::Bool(false)),
+            1 => v.remove(0),
+            _ => Expr::Or(v),
+        }
+    }
+
+    fn prec(&self) -> u8 {
+        match self {
+            Expr::Arrow(..) => 2,
+            Expr::Or(..) => 4,
+            Expr::And(..) => 5,
+            Expr::Binary(_, BinOp::Eq3, _) | Expr::Binary(_, BinOp::NotEq3, _) => 10,
+            Expr::Binary(_, BinOp::In, _)
+            | Expr::Binary(_, BinOp::InstanceOf, _)
+            | Expr::AsConst(..) => 11,
+            Expr::Binary(_, BinOp::Add, _) => 13,
+            Expr::Not(..) | Expr::Typeof(..) | Expr::Plus(..) => 15,
+            Expr::Member(..) | Expr::Index(..) | Expr::Call(..) | Expr::TypeArgs(..) => 18,
+            _ => 20,
+        }
+    }
+}
+
+impl Decl {
+    /// `export type FooInput<T> = Foo<T>`: the `Input` type of a declared type
+    /// that has none of its own. `None` for values
+    pub fn input_alias(&self) -> Option<Decl> {
+        let (name, type_params) = match self {
+            Decl::Type {
+                name, type_params, ..
+            }
+            | Decl::Interface {
+                name, type_params, ..
+            } => (name, type_params.clone()),
+            Decl::Enum { name, .. } => (name, vec![]),
+            _ => return None,
+        };
+        let args = type_params
+            .iter()
+            .map(|t| Type::named(t.name.as_str()))
+            .collect();
+        Some(Decl::Type {
+            docs: vec![],
+            export: true,
+            name: format!("{}Input", name),
+            type_params,
+            ty: Type::Ref(name.clone(), args),
+        })
+    }
+}
+
+/// Renders the tree. Objects stay on one line unless a member is
+/// documented or is a method, or they don't fit in the `line_width`.
+struct Printer {
+    out: String,
+    level: usize,
+    format: TsFormat,
+}
+
+impl Printer {
+    fn new(format: &TsFormat) -> Printer {
+        Printer {
+            out: String::new(),
+            level: 0,
+            format: *format,
+        }
+    }
+
+    fn push(&mut self, s: &str) {
+        self.out.push_str(s);
+    }
+
+    fn newline(&mut self) {
+        self.out.push('\n');
+        for _ in 0..self.level * self.format.indent {
+            self.out.push(' ');
+        }
+    }
+
+    /// does what `render` prints (on one line) fit in the `line_width`?
+    fn fits(&self, render: impl FnOnce(&mut Printer)) -> bool {
+        let width = match self.format.line_width {
+            Some(width) => width,
+            None => return true,
+        };
+        let mut p = Printer::new(&TsFormat {
+            line_width: None,
+            ..self.format
+        });
+        p.level = self.level;
+        render(&mut p);
+        let column = self.out.rsplit('\n').next().unwrap_or("").chars().count();
+        let first = p.out.split('\n').next().unwrap_or("").chars().count();
+        column + first <= width
+    }
+
+    fn docs(&mut self, docs: &[String]) {
+        if docs.is_empty() {
+            return;
+        }
+        self.push("/**");
+        for line in docs {
+            self.newline();
+            if line.is_empty() {
+                self.push(" *");
+            } else {
+                self.push(" * ");
+                self.push(line);
+            }
+        }
+        self.newline();
+        self.push(" */");
+        self.newline();
+    }
+
+    fn lit(&mut self, lit: &Lit) {
+        match lit {
+            Lit::Str(s) => self.string(s),
+            Lit::Num(n) => self.push(n),
+            Lit::Bool(b) => self.push(if *b { "true" } else { "false" }),
+        }
+    }
+
+    fn string(&mut self, s: &str) {
+        let quote = match self.format.quote {
+            Quote::Double => '"',
+            Quote::Single => '\'',
+        };
+        self.out.push(quote);
+        for c in s.chars() {
+            match c {
+                c if c == quote => {
+                    self.out.push('\\');
+                    self.out.push(c);
+                }
+                '\\' => self.push("\\\\"),
+                '\n' => self.push("\\n"),
+                '\r' => self.push("\\r"),
+                '\t' => self.push("\\t"),
+                c if c.is_control() => self.push(&format!("\\u{:04x}", c as u32)),
+                c => self.out.push(c),
+            }
+        }
+        self.out.push(quote);
+    }
+
+    fn key(&mut self, key: &str) {
+        if is_ident(key) {
+            self.push(key)
+        } else {
+            self.string(key)
+        }
+    }
+
+    fn list<T>(&mut self, items: &[T], sep: &str, f: &mut dyn FnMut(&mut Self, &T)) {
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                self.push(sep);
+            }
+            f(self, item);
+        }
+    }
+
+    /// `{ a; b }` or one item per line, each followed by `sep` (a last `,` only
+    /// with `trailing_comma`)
+    fn block<T>(
+        &mut self,
+        items: &[T],
+        multiline: bool,
+        sep: &str,
+        f: &mut dyn FnMut(&mut Self, &T),
+    ) {
+        if items.is_empty() {
+            self.push("{}");
+            return;
+        }
+        let inline = |p: &mut Self, f: &mut dyn FnMut(&mut Self, &T)| {
+            p.push("{ ");
+            p.list(items, &format!("{} ", sep), f);
+            p.push(" }");
+        };
+        if multiline || !self.fits(|p| inline(p, f)) {
+            self.push("{");
+            self.level += 1;
+            for (i, item) in items.iter().enumerate() {
+                self.newline();
+                f(self, item);
+                if sep != "," || self.format.trailing_comma || i + 1 < items.len() {
+                    self.push(sep);
+                }
+            }
+            self.level -= 1;
+            self.newline();
+            self.push("}");
+        } else {
+            inline(self, f);
+        }
+    }
+
+    /// `| A` on a line of its own for each type, one level in
+    fn union_lines(&mut self, types: &[Type]) {
+        self.level += 1;
+        for t in types {
+            self.newline();
+            self.push("| ");
+            self.ty(t, 1);
+        }
+        self.level -= 1;
+    }
+
+    fn type_params(&mut self, params: &[TypeParam]) {
+        if params.is_empty() {
+            return;
+        }
+        self.push("<");
+        self.list(params, ", ", &mut |p, t| {
+            p.push(&t.name);
+            if let Some(ref bound) = t.bound {
+                p.push(" extends ");
+                p.ty(bound, 0);
+            }
+            if let Some(ref default) = t.default {
+                p.push(" = ");
+                p.ty(default, 0);
+            }
+        });
+        self.push(">");
+    }
+
+    fn params(&mut self, params: &[Param]) {
+        self.push("(");
+        self.list(params, ", ", &mut |p, param| {
+            p.push(&param.name);
+            if let Some(ref ty) = param.ty {
+                p.push(": ");
+                p.ty(ty, 0);
+            }
+        });
+        self.push(")");
+    }
+
+    fn ty(&mut self, ty: &Type, prec: u8) {
+        if ty.prec() < prec {
+            self.push("(");
+            self.ty(ty, 0);
+            self.push(")");
+            return;
+        }
+        match ty {
+            Type::Ref(name, args) => {
+                self.push(name);
+                if !args.is_empty() {
+                    self.push("<");
+                    self.list(args, ", ", &mut |p, t| p.ty(t, 0));
+                    self.push(">");
+                }
+            }
+            Type::Lit(lit) => self.lit(lit),
+            Type::Array(elem) => {
+                self.ty(elem, 4);
+                self.push("[]");
+            }
+            Type::Tuple(v) => {
+                self.push("[");
+                self.list(v, ", ", &mut |p, t| p.ty(t, 0));
+                self.push("]");
+            }
+            Type::Object(members) => self.members(members, false),
+            Type::Union(v) if v.is_empty() => self.push("never"),
+            Type::Union(v) => self.list(v, " | ", &mut |p, t| p.ty(t, 1)),
+            Type::Intersection(v) if v.is_empty() => self.push("unknown"),
+            Type::Intersection(v) => self.list(v, " & ", &mut |p, t| p.ty(t, 2)),
+            Type::Function(params, ret) => {
+                self.params(params);
+                self.push(" => ");
+                self.ty(ret, 0);
+            }
+            Type::Predicate(name, ty) => {
+                self.push(name);
+                self.push(" is ");
+                self.ty(ty, 0);
+            }
+            Type::Typeof(name) => {
+                self.push("typeof ");
+                self.push(name);
+            }
+            Type::Keyof(ty) => {
+                self.push("keyof ");
+                self.ty(ty, 3);
+            }
+            Type::Readonly(ty) => {
+                self.push("readonly ");
+                self.ty(ty, 4);
+            }
+            Type::IndexedAccess(obj, index) => {
+                self.ty(obj, 4);
+                self.push("[");
+                self.ty(index, 0);
+                self.push("]");
+            }
+            Type::Raw(s) => self.push(s),
+        }
+    }
+
+    fn members(&mut self, members: &[Member], multiline: bool) {
+        let multiline = multiline
+            || members.iter().any(|m| match m {
+                Member::Property { docs, .. } => !docs.is_empty(),
+                Member::Method { .. } => true,
+                Member::Index { .. } => false,
+            });
+        let sep = match self.format.separator {
+            Separator::Semicolon => ";",
+            Separator::Comma => ",",
+        };
+        self.block(members, multiline, sep, &mut |p, m| match m {
+            Member::Property {
+                docs,
+                key,
+                optional,
+                readonly,
+                ty,
+            } => {
+                p.docs(docs);
+                if *readonly {
+                    p.push("readonly ");
+                }
+                p.key(key);
+                if *optional {
+                    p.push("?");
+                }
+                match ty {
+                    Type::Union(v)
+                        if v.len() > 1
+                            && !p.fits(|p| {
+                                p.push(": ");
+                                p.ty(ty, 0)
+                            }) =>
+                    {
+                        p.push(":");
+                        p.union_lines(v);
+                    }
+                    ty => {
+                        p.push(": ");
+                        p.ty(ty, 0);
+                    }
+                }
+            }
+            Member::Index { key, value } => {
+                p.push("[key: ");
+                p.ty(key, 0);
+                p.push("]: ");
+                p.ty(value, 0);
+            }
+            Member::Method {
+                name,
+                type_params,
+                params,
+                ret,
+            } => {
+                p.key(name);
+                p.type_params(type_params);
+                p.params(params);
+                p.push(": ");
+                p.ty(ret, 0);
+            }
+        });
+    }
+
+    fn expr(&mut self, e: &Expr, prec: u8) {
+        if e.prec() < prec {
+            self.push("(");
+            self.expr(e, 0);
+            self.push(")");
+            return;
+        }
+        match e {
+            Expr::Ident(s) => self.push(s),
+            Expr::Lit(lit) => self.lit(lit),
+            Expr::Member(obj, name) => {
+                self.expr(obj, 18);
+                if is_ident(name) {
+                    self.push(".");
+                    self.push(name);
+                } else {
+                    self.push("[");
+                    self.string(name);
+                    self.push("]");
+                }
+            }
+            Expr::Index(obj, index) => {
+                self.expr(obj, 18);
+                self.push("[");
+                self.expr(index, 0);
+                self.push("]");
+            }
+            Expr::Call(f, args) => {
+                self.expr(f, 18);
+                self.push("(");
+                self.list(args, ", ", &mut |p, a| p.expr(a, 2));
+                self.push(")");
+            }
+            Expr::TypeArgs(f, args) => {
+                self.expr(f, 18);
+                self.push("<");
+                self.list(args, ", ", &mut |p, t| p.ty(t, 0));
+                self.push(">");
+            }
+            Expr::Arrow(f) => {
+                self.type_params(&f.type_params);
+                self.params(&f.params);
+                if let Some(ref ret) = f.ret {
+                    self.push(": ");
+                    self.ty(ret, 0);
+                }
+                self.push(" => ");
+                match f.body {
+                    // not a block
+                    Body::Expr(ref body @ Expr::Object(..)) => {
+                        self.push("(");
+                        self.expr(body, 0);
+                        self.push(")");
+                    }
+                    Body::Expr(ref body) => self.expr(body, 2),
+                    Body::Block(ref stmts) => self.stmts(stmts),
+                }
+            }
+            Expr::Not(e) => {
+                self.push("!");
+                self.expr(e, 15);
+            }
+            Expr::Typeof(e) => {
+                self.push("typeof ");
+                self.expr(e, 15);
+            }
+            Expr::Plus(e) => {
+                self.push("+");
+                self.expr(e, 15);
+            }
+            Expr::Binary(l, op, r) => {
+                let prec = e.prec();
+                self.expr(l, prec);
+                self.push(match op {
+                    BinOp::Eq3 => " === ",
+                    BinOp::NotEq3 => " !== ",
+                    BinOp::In => " in ",
+                    BinOp::InstanceOf => " instanceof ",
+                    BinOp::Add => " + ",
+                });
+                self.expr(r, prec + 1);
+            }
+            Expr::And(v) => self.list(v, " && ", &mut |p, e| p.expr(e, 5)),
+            // `a && b || c` is correct but hard to read
+            Expr::Or(v) => self.list(v, " || ", &mut |p, e| p.expr(e, 6)),
+            Expr::Array(v) => {
+                self.push("[");
+                self.list(v, ", ", &mut |p, e| p.expr(e, 2));
+                self.push("]");
+            }
+            Expr::Object(props) => {
+                let multiline = props.iter().any(|prop| match prop {
+                    Prop::Value { docs, .. } => !docs.is_empty(),
+                    Prop::Method { .. } => true,
+                    Prop::Spread(..) => false,
+                });
+                self.block(props, multiline, ",", &mut |p, prop| match prop {
+                    Prop::Value { docs, key, value } => {
+                        p.docs(docs);
+                        p.key(key);
+                        p.push(": ");
+                        p.expr(value, 2);
+                    }
+                    Prop::Spread(value) => {
+                        p.push("...");
+                        p.expr(value, 2);
+                    }
+                    Prop::Method { name, function } => {
+                        p.key(name);
+                        p.function(function);
+                    }
+                });
+            }
+            Expr::AsConst(e) => {
+                self.expr(e, 12);
+                self.push(" as const");
+            }
+        }
+    }
+
+    /// `<T>(a: A): R { ... }`
+    fn function(&mut self, f: &Function) {
+        self.type_params(&f.type_params);
+        self.params(&f.params);
+        if let Some(ref ret) = f.ret {
+            self.push(": ");
+            self.ty(ret, 0);
+        }
+        self.push(" ");
+        match f.body {
+            Body::Block(ref stmts) => self.stmts(stmts),
+            Body::Expr(ref e) => self.stmts(&[Stmt::Return(e.clone())]),
+        }
+    }
+
+    fn stmts(&mut self, stmts: &[Stmt]) {
+        self.push("{");
+        self.level += 1;
+        for stmt in stmts {
+            self.newline();
+            match stmt {
+                Stmt::Return(e) => {
+                    self.push("return ");
+                    self.expr(e, 0);
+                    self.push(";");
+                }
+                Stmt::Comment(s) => {
+                    self.push("//");
+                    self.push(s);
+                }
+            }
+        }
+        self.level -= 1;
+        self.newline();
+        self.push("}");
+    }
+
+    fn decl(&mut self, decl: &Decl) {
+        match decl {
+            Decl::Type {
+                docs,
+                export,
+                name,
+                type_params,
+                ty,
+            } => {
+                self.docs(docs);
+                if *export {
+                    self.push("export ");
+                }
+                self.push("type ");
+                self.push(name);
+                self.type_params(type_params);
+                self.push(" =");
+                match ty {
+                    // a tagged union, or one that doesn't fit: one variant per line
+                    Type::Union(v)
+                        if v.iter()
+                            .any(|t| matches!(t, Type::Object(..) | Type::Intersection(..)))
+                            || v.len() > 1
+                                && !self.fits(|p| {
+                                    p.push(" ");
+                                    p.ty(ty, 0)
+                                }) =>
+                    {
+                        self.union_lines(v)
+                    }
+                    ty => {
+                        self.push(" ");
+                        self.ty(ty, 0);
+                    }
+                }
+            }
+            Decl::Enum {
+                docs,
+                is_const,
+                name,
+                members,
+            } => {
+                self.docs(docs);
+                self.push(if *is_const {
+                    "export const enum "
+                } else {
+                    "export enum "
+                });
+                self.push(name);
+                self.push(" ");
+                let multiline = members.iter().any(|m| !m.docs.is_empty());
+                self.block(members, multiline, ",", &mut |p, m| {
+                    p.docs(&m.docs);
+                    p.key(&m.name);
+                    p.push(" = ");
+                    p.lit(&m.value);
+                });
+            }
+            Decl::Interface {
+                docs,
+                name,
+                type_params,
+                members,
+            } => {
+                self.docs(docs);
+                self.push("export interface ");
+                self.push(name);
+                self.type_params(type_params);
+                self.push(" ");
+                self.members(members, true);
+            }
+            Decl::Const {
+                docs,
+                name,
+                ty,
+                value,
+            } => {
+                self.docs(docs);
+                self.push("export const ");
+                self.push(name);
+                if let Some(ty) = ty {
+                    self.push(": ");
+                    self.ty(ty, 0);
+                }
+                self.push(" = ");
+                self.expr(value, 0);
+            }
+            Decl::Function {
+                docs,
+                name,
+                function,
+            } => {
+                self.docs(docs);
+                self.push("export function ");
+                self.push(name);
+                self.function(function);
+            }
+            Decl::Namespace { name, decls } => {
+                self.push("export namespace ");
+                self.push(name);
+                self.push(" {");
+                self.level += 1;
+                for decl in decls {
+                    self.newline();
+                    self.decl(decl);
+                }
+                self.level -= 1;
+                self.newline();
+                self.push("}");
+            }
+        }
+    }
+}
+
+/// `!self`
+impl std::ops::Not for Expr {
+    type Output = Expr;
+
+    fn not(self) -> Expr {
+        Expr::Not(Box::new(self))
+    }
+}
+
+impl Type {
+    /// print with `format` (`to_string()` prints with the default)
+    pub fn print(&self, format: &TsFormat) -> String {
+        let mut p = Printer::new(format);
+        p.ty(self, 0);
+        p.out
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.print(&TsFormat::default()))
+    }
+}
+
+impl Expr {
+    /// print with `format` (`to_string()` prints with the default)
+    pub fn print(&self, format: &TsFormat) -> String {
+        let mut p = Printer::new(format);
+        p.expr(self, 0);
+        p.out
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.print(&TsFormat::default()))
+    }
+}
+
+impl Decl {
+    /// print with `format` (`to_string()` prints with the default)
+    pub fn print(&self, format: &TsFormat) -> String {
+        let mut p = Printer::new(format);
+        p.decl(self);
+        p.out
+    }
+}
+
+impl fmt::Display for Decl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.print(&TsFormat::default()))
+    }
+}
+
+/// A small recursive descent parser for typescript types.
+struct TypeParser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl TypeParser {
+    fn parse(s: &str) -> Result<Type, String> {
+        let mut p = TypeParser {
+            tokens: Self::lex(s)?,
+            pos: 0,
+        };
+        let ty = p.parse_union()?;
+        match p.peek() {
+            None => Ok(ty),
+            Some(t) => Err(format!("unexpected \"{}\"", t)),
+        }
+    }
+
+    /// strings are lexed as `"` followed by their (unescaped) value
+    fn lex(s: &str) -> Result<Vec<String>, String> {
+        let mut tokens = vec![];
+        let mut chars = s.chars().peekable();
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+            } else if c == '"' || c == '\'' {
+                let mut tok = String::from("\"");
+                chars.next();
+                loop {
+                    match chars.next() {
+                        Some(x) if x == c => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => tok.push('\n'),
+                            Some('t') => tok.push('\t'),
+                            Some(x) => tok.push(x),
+                            None => return Err("unterminated string".into()),
+                        },
+                        Some(x) => tok.push(x),
+                        None => return Err("unterminated string".into()),
+                    }
+                }
+                tokens.push(tok);
+            } else if c.is_alphanumeric() || c == '_' || c == '$' || c == '-' || c == '.' {
+                let mut tok = String::new();
+                while let Some(&x) = chars.peek() {
+                    if x.is_alphanumeric() || x == '_' || x == '$' || x == '-' || x == '.' {
+                        tok.push(x);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(tok);
+            } else {
+                tokens.push(c.to_string());
+                chars.next();
+            }
+        }
+        Ok(tokens)
+    }
+
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(|s| s.as_ref())
+    }
+
+    fn next(&mut self) -> Result<String, String> {
+        let t = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| "unexpected end of type".to_string())?;
+        self.pos += 1;
+        Ok(t)
+    }
+
+    fn eat(&mut self, tok: &str) -> bool {
+        if self.peek() == Some(tok) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, tok: &str) -> Result<(), String> {
+        if self.eat(tok) {
+            Ok(())
+        } else {
+            Err(format!("expected \"{}\"", tok))
+        }
+    }
+
+    fn parse_union(&mut self) -> Result<Type, String> {
+        self.eat("|"); // leading bar
+        let mut v = vec![self.parse_intersection()?];
+        while self.eat("|") {
+            v.push(self.parse_intersection()?);
+        }
+        Ok(if v.len() == 1 {
+            v.remove(0)
+        } else {
+            Type::Union(v)
+        })
+    }
+
+    fn parse_intersection(&mut self) -> Result<Type, String> {
+        let mut v = vec![self.parse_postfix()?];
+        while self.eat("&") {
+            v.push(self.parse_postfix()?);
+        }
+        Ok(if v.len() == 1 {
+            v.remove(0)
+        } else {
+            Type::Intersection(v)
+        })
+    }
+
+    fn parse_postfix(&mut self) -> Result<Type, String> {
+        if self.eat("readonly") {
+            return Ok(Type::Readonly(Box::new(self.parse_postfix()?)));
+        }
+        let mut ty = self.parse_primary()?;
+        while self.peek() == Some("[")
+            && self.tokens.get(self.pos + 1).map(|s| s.as_ref()) == Some("]")
+        {
+            self.pos += 2;
+            ty = Type::array(ty);
+        }
+        Ok(ty)
+    }
+
+    fn parse_list(&mut self, close: &str) -> Result<Vec<Type>, String> {
+        let mut v = vec![];
+        while !self.eat(close) {
+            v.push(self.parse_union()?);
+            if !self.eat(",") {
+                self.expect(close)?;
+                break;
+            }
+        }
+        Ok(v)
+    }
+
+    fn parse_member(&mut self) -> Result<Member, String> {
+        if self.eat("[") {
+            // `[key: K]: V`
+            self.next()?;
+            self.expect(":")?;
+            let key = self.parse_union()?;
+            self.expect("]")?;
+            self.expect(":")?;
+            let value = self.parse_union()?;
+            return Ok(Member::Index { key, value });
+        }
+        // `readonly: T` is a property called readonly
+        let readonly = self.peek() == Some("readonly")
+            && !matches!(
+                self.tokens.get(self.pos + 1).map(|s| s.as_ref()),
+                Some(":") | Some("?")
+            );
+        if readonly {
+            self.pos += 1;
+        }
+        let key = self.next()?;
+        let key = key.strip_prefix('"').unwrap_or(&key).to_string();
+        let optional = self.eat("?");
+        self.expect(":")?;
+        Ok(Member::Property {
+            docs: vec![],
+            key,
+            optional,
+            readonly,
+            ty: self.parse_union()?,
+        })
+    }
+
+    fn parse_primary(&mut self) -> Result<Type, String> {
+        let tok = self.next()?;
+        match tok.as_ref() {
+            "(" => {
+                let ty = self.parse_union()?;
+                self.expect(")")?;
+                Ok(ty)
+            }
+            "[" => Ok(Type::Tuple(self.parse_list("]")?)),
+            "{" => {
+                let mut members = vec![];
+                while !self.eat("}") {
+                    members.push(self.parse_member()?);
+                    if !self.eat(";") && !self.eat(",") {
+                        self.expect("}")?;
+                        break;
+                    }
+                }
+                Ok(Type::Object(members))
+            }
+            t if t.starts_with('"') => Ok(Type::str(&t[1..])),
+            "true" => Ok(Type::Lit(Lit::Bool(true))),
+            "false" => Ok(Type::Lit(Lit::Bool(false))),
+            t if t.starts_with(|c: char| c.is_ascii_digit() || c == '-') => {
+                Ok(Type::Lit(Lit::Num(tok)))
+            }
+            t if t.starts_with(|c: char| c.is_alphabetic() || c == '_' || c == '$') => {
+                let args = if self.eat("<") {
+                    self.parse_list(">")?
+                } else {
+                    vec![]
+                };
+                Ok(Type::Ref(tok, args))
+            }
+            t => Err(format!("unexpected \"{}\"", t)),
+        }
+    }
+}
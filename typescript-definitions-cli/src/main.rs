@@ -0,0 +1,582 @@
+// Copyright 2019 Ian Castleden
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `typescript-definitions`: generate typescript from crate sources without building them.
+//!
+//! Scans `.rs` files for `#[derive(TypeScriptify)]` or `#[derive(TypeScriptDefinition)]`
+//! items and runs them through the same pipeline as the derive macros. Type mappings
+//! are read from the `typescript-definitions.toml` next to the `Cargo.toml` above each PATH.
+//!
+//! ```text
+//! typescript-definitions [--check] [-o FILE] [PATH ...]
+//! ```
+
+use quote::ToTokens;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::{env, fs, panic, process};
+use typescript_definitions_core::{Config, Typescriptify, JSON_VALUE, JSON_VALUE_DEFINITION};
+
+const USAGE: &str = "usage: typescript-definitions [--check] [-o FILE] [PATH ...]
+
+Writes the typescript for every `#[derive(TypeScriptify)]` or
+`#[derive(TypeScriptDefinition)]` type found in the .rs files under
+PATH (default `src`) to FILE (default stdout). Type mappings are read
+from the typescript-definitions.toml next to the Cargo.toml above PATH.
+
+    -o, --output FILE  file to write
+    --check            don't write FILE, exit with 1 if it is out of date";
+
+struct Args {
+    check: bool,
+    output: Option<PathBuf>,
+    paths: Vec<PathBuf>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        check: false,
+        output: None,
+        paths: vec![],
+    };
+    let mut it = env::args().skip(1);
+    while let Some(arg) = it.next() {
+        match arg.as_ref() {
+            "--check" => args.check = true,
+            "-o" | "--output" => match it.next() {
+                Some(file) => args.output = Some(file.into()),
+                None => return Err(format!("{} requires a FILE", arg)),
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0)
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+            _ => args.paths.push(arg.into()),
+        }
+    }
+    if args.check && args.output.is_none() {
+        return Err("--check requires --output FILE".into());
+    }
+    if args.paths.is_empty() {
+        args.paths.push("src".into());
+    }
+    Ok(args)
+}
+
+/// all the `.rs` files under `path`, in a stable order
+fn rust_files(path: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if path.is_dir() {
+        let mut entries = fs::read_dir(path)?
+            .map(|e| e.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        entries.sort();
+        for entry in entries {
+            rust_files(&entry, files)?;
+        }
+    } else if path.extension() == Some("rs".as_ref()) {
+        files.push(path.to_owned());
+    }
+    Ok(())
+}
+
+fn is_derive(meta: &syn::Meta) -> bool {
+    match meta {
+        syn::Meta::List(list) if list.path.is_ident("derive") => {
+            list.nested.iter().any(|nested| match nested {
+                syn::NestedMeta::Meta(syn::Meta::Path(path)) => match path.segments.last() {
+                    Some(s) => s.ident == "TypeScriptify" || s.ident == "TypeScriptDefinition",
+                    None => false,
+                },
+                _ => false,
+            })
+        }
+        // #[cfg_attr(feature = "...", derive(TypeScriptify))]
+        syn::Meta::List(list) if list.path.is_ident("cfg_attr") => {
+            list.nested.iter().skip(1).any(|nested| match nested {
+                syn::NestedMeta::Meta(meta) => is_derive(meta),
+                _ => false,
+            })
+        }
+        _ => false,
+    }
+}
+
+fn is_typescriptify(attrs: &[syn::Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter_map(|attr| attr.parse_meta().ok())
+        .any(|meta| is_derive(&meta))
+}
+
+fn is_cfg_test(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path.is_ident("cfg") && attr.tokens.to_string().replace(' ', "") == "(test)"
+    })
+}
+
+/// `#[path = "..."]` on a `mod`
+fn path_attr(attrs: &[syn::Attribute]) -> Option<String> {
+    attrs
+        .iter()
+        .filter_map(|attr| attr.parse_meta().ok())
+        .find_map(|meta| match meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                ref path,
+                lit: syn::Lit::Str(ref value),
+                ..
+            }) if path.is_ident("path") => Some(value.value()),
+            _ => None,
+        })
+}
+
+/// where the `mod x;` declared in `path` live: `src/a/x.rs` for `src/a.rs`
+/// or `src/a/mod.rs` (and `src/x.rs` for `src/lib.rs` or `src/main.rs`)
+fn module_dir(path: &Path) -> PathBuf {
+    let dir = path.parent().unwrap_or_else(|| Path::new("")).to_owned();
+    match path.file_stem().and_then(|stem| stem.to_str()) {
+        Some("mod") | Some("lib") | Some("main") | None => dir,
+        Some(stem) => dir.join(stem),
+    }
+}
+
+/// the files and directories of the out of line modules in `#[cfg(test)]`
+/// modules of `items`, whose modules live in `dir`
+fn test_modules(items: &[syn::Item], file: &Path, dir: &Path, found: &mut Vec<PathBuf>) {
+    for item in items {
+        let m = match item {
+            syn::Item::Mod(m) => m,
+            _ => continue,
+        };
+        let name = m.ident.to_string();
+        match m.content {
+            // an inline test module: everything it declares lives under `dir/name`
+            Some(_) if is_cfg_test(&m.attrs) => found.push(dir.join(&name)),
+            Some((_, ref items)) => test_modules(items, file, &dir.join(&name), found),
+            None if is_cfg_test(&m.attrs) => match path_attr(&m.attrs) {
+                Some(path) => found.push(file.parent().unwrap_or(dir).join(path)),
+                None => {
+                    found.push(dir.join(format!("{}.rs", name)));
+                    found.push(dir.join(&name));
+                }
+            },
+            None => {}
+        }
+    }
+}
+
+/// the directory of the nearest `Cargo.toml` above `path` (else the current directory)
+fn crate_dir(path: &Path) -> std::io::Result<PathBuf> {
+    let path = fs::canonicalize(path)?;
+    match path
+        .ancestors()
+        .find(|dir| dir.join("Cargo.toml").is_file())
+    {
+        Some(dir) => Ok(dir.to_owned()),
+        None => env::current_dir(),
+    }
+}
+
+/// the derived items in `items` (and any inline modules)
+fn derived_items(items: &[syn::Item], found: &mut Vec<proc_macro2::TokenStream>) {
+    for item in items {
+        match item {
+            syn::Item::Struct(s) if is_typescriptify(&s.attrs) => found.push(s.to_token_stream()),
+            syn::Item::Enum(e) if is_typescriptify(&e.attrs) => found.push(e.to_token_stream()),
+            syn::Item::Mod(m) if !is_cfg_test(&m.attrs) => {
+                if let Some((_, ref items)) = m.content {
+                    derived_items(items, found)
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// `file:line:column: message`
+fn located(path: &Path, err: &syn::Error) -> String {
+    let start = err.span().start();
+    format!(
+        "{}:{}:{}: {}",
+        path.display(),
+        start.line,
+        start.column + 1,
+        err
+    )
+}
+
+/// a derived item run through the derive pipeline
+struct Generated {
+    name: String,
+    /// the definition followed by the `Input` shape
+    ts: String,
+    /// `type FooInput = Foo`, for the `Input` types referencing us when we have none
+    alias: Option<String>,
+    dependencies: Vec<String>,
+    /// the types referenced by their `Input` name
+    input_dependencies: Vec<String>,
+}
+
+/// run an item through the derive pipeline
+fn typescriptify(
+    path: &Path,
+    item: proc_macro2::TokenStream,
+    config: &Arc<Config>,
+) -> Result<Generated, String> {
+    // the pipeline reports errors at their spans, but can still panic on input rustc would reject
+    panic::catch_unwind(|| {
+        let tsy = Typescriptify::with_config(item, config.clone());
+        let mut parsed = tsy.parse();
+        let mut parsed_input = if tsy.wants_input() {
+            Some(tsy.parse_input())
+        } else {
+            None
+        };
+        let input_checked = parsed_input.as_mut().map_or(Ok(()), |p| p.check());
+        if let Err(errors) = parsed.check().and(input_checked) {
+            let errors = errors.iter().map(|err| located(path, err));
+            return Err(errors.collect::<Vec<_>>().join("\n"));
+        }
+        let mut ts = parsed.type_script_ify_source();
+        let input = parsed_input
+            .as_ref()
+            .map(|p| p.export_type_declaration_source())
+            .unwrap_or_default();
+        // as written by `typescript_definitions::export_all`
+        let alias = if input.is_empty() {
+            let decl = parsed.export_type_declaration_ast();
+            decl.input_alias().map(|alias| alias.to_string())
+        } else {
+            ts.push('\n');
+            ts.push_str(&input);
+            None
+        };
+        Ok(Generated {
+            name: parsed.ident.clone(),
+            ts,
+            alias,
+            dependencies: parsed.dependencies(),
+            input_dependencies: parsed_input
+                .as_ref()
+                .map(|p| p.input_dependencies())
+                .unwrap_or_default(),
+        })
+    })
+    .unwrap_or_else(|err| {
+        let err = err
+            .downcast_ref::<String>()
+            .cloned()
+            .or_else(|| err.downcast_ref::<&str>().map(|s| s.to_string()))
+            .unwrap_or_else(|| "unknown error".into());
+        Err(format!("{}: {}", path.display(), err))
+    })
+}
+
+/// The typescript for all the derived types in `files` (each with the config of its crate):
+/// sorted by name with duplicates removed, the same as `typescript_definitions::export_all`.
+fn generate(files: &[(PathBuf, String, Arc<Config>)]) -> Result<String, String> {
+    let mut parsed = vec![];
+    let mut tests = vec![];
+    for (path, source, config) in files {
+        let file = syn::parse_file(source).map_err(|err| located(path, &err))?;
+        test_modules(&file.items, path, &module_dir(path), &mut tests);
+        parsed.push((path, file, config));
+    }
+    let mut types = BTreeMap::new();
+    let mut aliased = BTreeSet::new();
+    // skip the files of `#[cfg(test)] mod tests;` as well
+    let parsed = parsed
+        .into_iter()
+        .filter(|(path, ..)| !tests.iter().any(|test| path.starts_with(test)));
+    for (path, file, config) in parsed {
+        let mut found = vec![];
+        derived_items(&file.items, &mut found);
+        for item in found {
+            let generated = typescriptify(path, item, config)?;
+            aliased.extend(generated.input_dependencies);
+            let mut defined = vec![(generated.name, (generated.ts, generated.alias))];
+            // as registered by the derive macro
+            if generated.dependencies.iter().any(|dep| dep == JSON_VALUE) {
+                defined.push((JSON_VALUE.into(), (JSON_VALUE_DEFINITION.into(), None)));
+            }
+            for (name, ts) in defined {
+                match types.get(&name) {
+                    Some(prev) if *prev != ts => {
+                        return Err(format!(
+                            "{}: typescript type \"{}\" is defined more than once",
+                            path.display(),
+                            name
+                        ))
+                    }
+                    Some(_) => {}
+                    None => {
+                        types.insert(name, ts);
+                    }
+                }
+            }
+        }
+    }
+    let mut out = String::new();
+    for (name, (ts, alias)) in &types {
+        out.push_str(ts);
+        out.push('\n');
+        if let Some(alias) = alias.as_ref().filter(|_| aliased.contains(name)) {
+            out.push_str(alias);
+            out.push('\n');
+        }
+    }
+    Ok(out)
+}
+
+fn run(args: Args) -> Result<bool, String> {
+    let mut paths = vec![];
+    for path in &args.paths {
+        let mut found = vec![];
+        rust_files(path, &mut found).map_err(|err| format!("{}: {}", path.display(), err))?;
+        paths.extend(found.into_iter().map(|file| (file, path)));
+    }
+    let mut files = vec![];
+    for (path, root) in paths {
+        let source =
+            fs::read_to_string(&path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        // the same config as the derive macro, wherever we are run from
+        let dir = crate_dir(root).map_err(|err| format!("{}: {}", root.display(), err))?;
+        files.push((path, source, Config::load_dir(&dir)?));
+    }
+
+    // keep the panic messages to ourselves
+    panic::set_hook(Box::new(|_| {}));
+    let out = generate(&files)?;
+    let _ = panic::take_hook();
+
+    match args.output {
+        None => print!("{}", out),
+        Some(ref output) if args.check => {
+            let current = fs::read_to_string(output).unwrap_or_default();
+            if current != out {
+                eprintln!(
+                    "{} is out of date: rerun typescript-definitions without --check",
+                    output.display()
+                );
+                return Ok(false);
+            }
+        }
+        Some(ref output) => {
+            fs::write(output, out).map_err(|err| format!("{}: {}", output.display(), err))?
+        }
+    }
+    Ok(true)
+}
+
+fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("typescript-definitions: {}\n\n{}", err, USAGE);
+            process::exit(2)
+        }
+    };
+    match run(args) {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(err) => {
+            eprintln!("typescript-definitions: {}", err);
+            process::exit(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate_str(source: &str) -> Result<String, String> {
+        generate(&[("lib.rs".into(), source.into(), Arc::default())])
+    }
+
+    #[test]
+    fn finds_derived_items() {
+        let out = generate_str(
+            r#"
+            #[derive(Serialize, TypeScriptify)]
+            struct B { b: i32 }
+
+            #[derive(Serialize)]
+            struct Ignored { c: i32 }
+
+            mod inner {
+                #[cfg_attr(target_arch = "wasm32", derive(typescript_definitions::TypeScriptDefinition))]
+                struct A { a: String }
+            }
+
+            #[cfg(test)]
+            mod tests {
+                #[derive(Serialize, TypeScriptify)]
+                struct Test { t: i32 }
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            out,
+            "export type A = { a: string }\nexport const A = (check: A) => check\n\n\
+             export type B = { b: number }\nexport const B = (check: B) => check\n\n"
+        );
+    }
+
+    #[test]
+    fn exports_json_value() {
+        let out = generate_str(
+            r#"
+            #[derive(Serialize, TypeScriptify)]
+            struct Event { payload: serde_json::Value }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            out,
+            "export type Event = { payload: JsonValue }\nexport const Event = (check: Event) => check\n\n\
+             export type JsonValue = null | boolean | number | string | JsonValue[] | { [key: string]: JsonValue }\n"
+        );
+    }
+
+    #[test]
+    fn reports_errors() {
+        let err = generate_str(
+            r#"
+            #[derive(Serialize, TypeScriptify)]
+            struct S { #[ts(ts_type = "{")] a: i32 }
+            "#,
+        )
+        .unwrap_err();
+        assert_eq!(err, "lib.rs:3:24: S: can't parse type {");
+    }
+
+    #[test]
+    fn reports_attribute_errors() {
+        let err = generate_str(
+            r#"
+            #[derive(Serialize, TypeScriptify)]
+            #[ts(enum_style = "nope")]
+            struct S { a: i32 }
+            "#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            "lib.rs:3:31: enum_style: \"nope\" is not one of \"enum\", \"union\", \"const_enum\" or \"const_object\""
+        );
+    }
+
+    #[test]
+    fn writes_input_types() {
+        let out = generate_str(
+            r#"
+            #[derive(Serialize, TypeScriptify)]
+            #[ts(input)]
+            struct A { a: String }
+
+            // the derive macro doesn't see `Deserialize`
+            #[derive(Serialize, Deserialize, TypeScriptify)]
+            struct B { b: i32 }
+
+            #[derive(Serialize, TypeScriptify)]
+            #[ts(input = false)]
+            struct C { c: i32 }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            out,
+            "export type A = { a: string }\nexport const A = (check: A) => check\n\n\
+             export type AInput = { a: string }\n\
+             export type B = { b: number }\nexport const B = (check: B) => check\n\n\
+             export type C = { c: number }\nexport const C = (check: C) => check\n\n"
+        );
+    }
+
+    #[test]
+    fn aliases_referenced_input_types() {
+        let out = generate_str(
+            r#"
+            #[derive(Serialize, TypeScriptify)]
+            #[ts(input)]
+            struct Query { filter: Filter }
+
+            #[derive(Serialize, TypeScriptify)]
+            struct Filter { name: String }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            out,
+            "export type Filter = { name: string }\nexport const Filter = (check: Filter) => check\n\n\
+             export type FilterInput = Filter\n\
+             export type Query = { filter: Filter }\nexport const Query = (check: Query) => check\n\n\
+             export type QueryInput = { filter: FilterInput }\n"
+        );
+    }
+
+    #[test]
+    fn serialize_only_ignores_deserialize_errors() {
+        // `default` fields can't be deserialized transparently
+        let source = r#"
+            #[derive(Serialize, TypeScriptify)]
+            #[serde(transparent)]
+            struct Id { #[serde(default)] id: i32 }
+            "#;
+        assert_eq!(
+            generate_str(source).unwrap(),
+            "export type Id = number\nexport const Id = (check: Id) => check\n\n"
+        );
+        let err = generate_str(&source.replace("TypeScriptify)]", "TypeScriptify)]\n#[ts(input)]"))
+            .unwrap_err();
+        assert!(
+            err.contains("#[serde(transparent)] requires at least one field"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn skips_out_of_line_test_modules() {
+        let file = |path: &str, source: &str| (path.into(), source.into(), Arc::default());
+        let derived =
+            |name: &str| format!("#[derive(Serialize, TypeScriptify)] struct {} {{}}", name);
+        let out = generate(&[
+            file(
+                "src/lib.rs",
+                "#[cfg(test)] mod tests; #[cfg(test)] #[path = \"fixtures.rs\"] mod fx; mod a;",
+            ),
+            file("src/tests.rs", &derived("T")),
+            file("src/tests/more.rs", &derived("M")),
+            file("src/fixtures.rs", &derived("F")),
+            file("src/a.rs", "#[cfg(test)] mod tests {}"),
+            file("src/a/tests/b.rs", &derived("B")),
+            file("src/a/c.rs", &derived("C")),
+        ])
+        .unwrap();
+        assert_eq!(
+            out,
+            "export type C = {}\nexport const C = (check: C) => check\n\n"
+        );
+    }
+
+    #[test]
+    fn config_of_the_crate() {
+        let dir = env::temp_dir().join(format!("tsy-cli-{}", process::id()));
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("Cargo.toml"), "").unwrap();
+        assert_eq!(
+            crate_dir(&dir.join("src")).unwrap(),
+            fs::canonicalize(&dir).unwrap()
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,630 @@
+// Copyright 2019 Ian Castleden
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::ts::Docs;
+use super::{ast, ident_from_str, Ctxt};
+use quote::{quote, ToTokens};
+
+use proc_macro2::TokenStream;
+use syn::{Attribute, Ident, Lit, Meta, /* MetaList,*/ MetaNameValue, NestedMeta};
+
+/// How `Option<T>` fields are written: `#[ts(option_style = "...")]`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OptionStyle {
+    /// `a: T | null` (what serde_json writes)
+    Null,
+    /// `a: T | undefined`
+    Undefined,
+    /// `a?: T`
+    Optional,
+}
+
+impl OptionStyle {
+    /// the `#[ts(option_style = "...")]` (or `typescript-definitions.toml`) value
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "null" => Ok(OptionStyle::Null),
+            "undefined" => Ok(OptionStyle::Undefined),
+            "optional" => Ok(OptionStyle::Optional),
+            v => Err(format!(
+                "\"{}\" is not one of \"null\", \"undefined\" or \"optional\"",
+                v
+            )),
+        }
+    }
+}
+
+/// How unit-only enums are written: `#[ts(enum_style = "...")]`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum EnumStyle {
+    /// `enum Color { Red = "Red" }`
+    #[default]
+    Enum,
+    /// `type Color = "Red" | "Green"`
+    Union,
+    /// `const enum Color { Red = "Red" }`
+    ConstEnum,
+    /// `const Color = { Red: "Red" } as const` and `type Color = (typeof Color)[keyof typeof Color]`
+    ConstObject,
+}
+
+impl EnumStyle {
+    /// the `#[ts(enum_style = "...")]` (or `typescript-definitions.toml`) value
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "enum" => Ok(EnumStyle::Enum),
+            "union" => Ok(EnumStyle::Union),
+            "const_enum" => Ok(EnumStyle::ConstEnum),
+            "const_object" => Ok(EnumStyle::ConstObject),
+            v => Err(format!(
+                "\"{}\" is not one of \"enum\", \"union\", \"const_enum\" or \"const_object\"",
+                v
+            )),
+        }
+    }
+}
+
+/// How `HashMap` and `BTreeMap` are written: `#[ts(map_style = "...")]`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MapStyle {
+    /// `Record<string, V>`, or `Partial<Record<K, V>>` when not every key is possible
+    /// (e.g. a unit enum key)
+    Record,
+    /// `{ [key: K]: V }`
+    Index,
+    /// `Map<K, V>` for formats that write a javascript `Map` (e.g. `serde-wasm-bindgen`)
+    EsMap,
+}
+
+impl MapStyle {
+    /// the `#[ts(map_style = "...")]` (or `typescript-definitions.toml`) value
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "record" => Ok(MapStyle::Record),
+            "index" => Ok(MapStyle::Index),
+            "es_map" => Ok(MapStyle::EsMap),
+            v => Err(format!(
+                "\"{}\" is not one of \"record\", \"index\" or \"es_map\"",
+                v
+            )),
+        }
+    }
+}
+
+/// How 64 and 128 bit integers are written: `#[ts(int64 = "...")]`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Int64 {
+    /// `number` (loses precision above 2^53)
+    #[default]
+    Number,
+    /// `bigint`
+    Bigint,
+    /// `string` (e.g. for `serde_with::DisplayFromStr`)
+    String,
+}
+
+impl Int64 {
+    /// the `#[ts(int64 = "...")]` (or `typescript-definitions.toml`) value
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "number" => Ok(Int64::Number),
+            "bigint" => Ok(Int64::Bigint),
+            "string" => Ok(Int64::String),
+            v => Err(format!(
+                "\"{}\" is not one of \"number\", \"bigint\" or \"string\"",
+                v
+            )),
+        }
+    }
+}
+
+/// How `serde_json::Value` is written: `#[ts(json_style = "...")]`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum JsonStyle {
+    /// `JsonValue`, a recursive type exported alongside
+    #[default]
+    JsonValue,
+    /// `unknown`
+    Unknown,
+}
+
+impl JsonStyle {
+    /// the `#[ts(json_style = "...")]` (or `typescript-definitions.toml`) value
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "json_value" => Ok(JsonStyle::JsonValue),
+            "unknown" => Ok(JsonStyle::Unknown),
+            v => Err(format!(
+                "\"{}\" is not one of \"json_value\" or \"unknown\"",
+                v
+            )),
+        }
+    }
+}
+
+/// The serde format the typescript describes: `#[ts(profile = "...")]`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Profile {
+    /// `serde_json`
+    #[default]
+    Json,
+    /// `serde-wasm-bindgen`: maps are `Map`s, 64 bit integers `bigint`s, bytes `Uint8Array`s
+    /// and `None` and unit structs `undefined`
+    WasmBindgen,
+    /// `rmp-serde` with named fields: bytes are `Uint8Array`s and unit structs `[]`
+    MessagePack,
+}
+
+impl Profile {
+    /// the `#[ts(profile = "...")]` (or `typescript-definitions.toml`) value
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "json" => Ok(Profile::Json),
+            "wasm_bindgen" => Ok(Profile::WasmBindgen),
+            "msgpack" => Ok(Profile::MessagePack),
+            v => Err(format!(
+                "\"{}\" is not one of \"json\", \"wasm_bindgen\" or \"msgpack\"",
+                v
+            )),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Attrs {
+    /// list of blocks of doc comment lines
+    comments: Vec<Vec<String>>,
+    pub ts_type: Option<String>,
+    pub ts_handler_name: Option<String>,
+    pub ts_handler_return: Option<String>,
+    pub ts_factory_name: Option<String>,
+    pub ts_factory_return_name: Option<String>,
+    pub ts_as: Option<syn::Type>,
+    pub ts_guard: Option<String>,
+    pub ts_optional: bool,
+    pub int64: Option<Int64>,
+    pub option_style: Option<OptionStyle>,
+    pub map_style: Option<MapStyle>,
+    pub enum_style: Option<EnumStyle>,
+    pub json_style: Option<JsonStyle>,
+    pub profile: Option<Profile>,
+    /// `#[ts(strict)]`: lossy conversions (e.g. to `any`) are errors
+    pub strict: Option<bool>,
+    /// `#[ts(readonly)]`: readonly properties, arrays and maps
+    pub readonly: Option<bool>,
+    /// `#[ts(input)]`: also write the `Input` type serde deserializes
+    pub input: Option<bool>,
+    /// `#[ts(tuple_limit = 4)]`: `[T; N]` arrays up to this length are tuples
+    pub tuple_limit: Option<usize>,
+    /// `#[ts(repr)]`: a `#[repr(..)]` enum serialized as its discriminant
+    pub ts_repr: bool,
+    /// `#[ts(bound = "T: Shape")]`: (type parameter, typescript constraint)
+    pub ts_bounds: Vec<(String, String)>,
+}
+
+#[inline]
+fn path_to_str(path: &syn::Path) -> String {
+    quote!(#path).to_string()
+}
+
+#[allow(unused)]
+pub fn turbofish_check(v: &str) -> Result<TokenStream, String> {
+    match v.parse::<proc_macro2::TokenStream>() {
+        // just get LexError as error message... so make our own.
+        Err(_) => Err(format!("Can't lex turbofish \"{}\"", v)),
+        Ok(tokens) => match syn::parse2::<syn::DeriveInput>(quote!( struct S{ a:v#tokens} )) {
+            Err(_) => Err(format!("Can't parse turbofish \"{}\"", v)),
+            Ok(_) => Ok(tokens),
+        },
+    }
+}
+impl Attrs {
+    pub fn new() -> Attrs {
+        Attrs {
+            comments: vec![],
+            // turbofish: None,
+            ts_type: None,
+            ts_handler_name: None,
+            ts_handler_return: None,
+            ts_factory_name: None,
+            ts_factory_return_name: None,
+            ts_as: None, // isa: HashMap::new(),
+            ts_guard: None,
+            ts_optional: false,
+            int64: None,
+            option_style: None,
+            map_style: None,
+            enum_style: None,
+            json_style: None,
+            profile: None,
+            strict: None,
+            readonly: None,
+            input: None,
+            tuple_limit: None,
+            ts_repr: false,
+            ts_bounds: vec![],
+        }
+    }
+    pub fn push_doc_comment(&mut self, attrs: &[Attribute]) {
+        let doc_comments = attrs
+            .iter()
+            .filter_map(|attr| {
+                if path_to_str(&attr.path) == "doc" {
+                    attr.parse_meta().ok()
+                } else {
+                    None
+                }
+            })
+            .filter_map(|attr| {
+                use Lit::*;
+                use Meta::*;
+                if let NameValue(MetaNameValue { lit: Str(s), .. }) = attr {
+                    let value = s.value();
+                    let text = value
+                        .trim_start_matches("//!")
+                        .trim_start_matches("///")
+                        .trim_start_matches("/*!")
+                        .trim_start_matches("/**")
+                        .trim_end_matches("*/")
+                        .trim();
+                    if text.is_empty() {
+                        None
+                    } else {
+                        Some(text.to_string())
+                    }
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if doc_comments.is_empty() {
+            return;
+        }
+
+        self.comments.push(doc_comments);
+    }
+
+    /// the lines of the `/** ... */` comment, with a blank line between blocks
+    pub fn docs(&self) -> Docs {
+        self.comments.join(&String::new())
+    }
+
+    fn err_msg<A: ToTokens>(&self, tokens: A, msg: String, ctxt: Option<&'_ Ctxt>) {
+        if let Some(ctxt) = ctxt {
+            ctxt.error_spanned_by(tokens, msg);
+        } else {
+            panic!("{}", msg)
+        };
+    }
+    pub fn find_typescript<'a>(
+        attrs: &'a [Attribute],
+        ctxt: Option<&'a Ctxt>,
+    ) -> impl Iterator<Item = Meta> + 'a {
+        use syn::Meta::*;
+        use NestedMeta::*;
+
+        fn err<A: quote::ToTokens>(tokens: A, msg: String, ctxt: Option<&'_ Ctxt>) {
+            if let Some(ctxt) = ctxt {
+                ctxt.error_spanned_by(tokens, format!("invalid typescript syntax: {}", msg));
+            } else {
+                panic!("invalid typescript syntax: {}", msg)
+            };
+        }
+
+        attrs
+            .iter()
+            .filter_map(move |attr| match path_to_str(&attr.path).as_ref() {
+                "ts" => match attr.parse_meta() {
+                    Ok(v) => Some(v),
+                    Err(msg) => {
+                        err(attr, msg.to_string(), ctxt);
+                        None
+                    }
+                },
+                _ => None,
+            })
+            .filter_map(move |m| match m {
+                List(l) => Some(l.nested),
+                ref tokens => {
+                    err(&m, quote!(#tokens).to_string(), ctxt);
+                    None
+                }
+            })
+            .flatten()
+            .filter_map(move |m| match m {
+                Meta(m) => Some(m),
+                ref tokens => {
+                    err(&m, quote!(#tokens).to_string(), ctxt);
+                    None
+                }
+            })
+    }
+    pub fn push_attrs(&mut self, _struct_ident: &Ident, attrs: &[Attribute], ctxt: Option<&Ctxt>) {
+        use syn::Meta::*;
+        use Lit::*;
+        // use NestedMeta::*;
+
+        for attr in Self::find_typescript(attrs, ctxt) {
+            match attr {
+                // #[ts(handler_name = "HandleFooBar")]
+                NameValue(MetaNameValue {
+                    ref path,
+                    lit: Str(ref value),
+                    ..
+                }) if is_path_ident(path, "handler_name") => {
+                    self.ts_handler_name = Some(value.value())
+                }
+                // #[ts(handler_return = "boolean")]
+                NameValue(MetaNameValue {
+                    ref path,
+                    lit: Str(ref value),
+                    ..
+                }) if is_path_ident(path, "handler_return") => {
+                    self.ts_handler_return = Some(value.value())
+                }
+                // #[ts(factory_name = "FooBar")]
+                NameValue(MetaNameValue {
+                    ref path,
+                    lit: Str(ref value),
+                    ..
+                }) if is_path_ident(path, "factory_name") => {
+                    self.ts_factory_name = Some(value.value())
+                }
+                // #[ts(factory_return_name = "FooBar")]
+                NameValue(MetaNameValue {
+                    ref path,
+                    lit: Str(ref value),
+                    ..
+                }) if is_path_ident(path, "factory_return_name") => {
+                    self.ts_factory_return_name = Some(value.value())
+                }
+                // #[ts(option_style = "optional")]
+                NameValue(MetaNameValue {
+                    ref path,
+                    lit: Str(ref value),
+                    ..
+                }) if is_path_ident(path, "option_style") => {
+                    match OptionStyle::parse(&value.value()) {
+                        Ok(style) => self.option_style = Some(style),
+                        Err(msg) => self.err_msg(value, format!("option_style: {}", msg), ctxt),
+                    }
+                }
+                // #[ts(enum_style = "union")]
+                NameValue(MetaNameValue {
+                    ref path,
+                    lit: Str(ref value),
+                    ..
+                }) if is_path_ident(path, "enum_style") => match EnumStyle::parse(&value.value()) {
+                    Ok(style) => self.enum_style = Some(style),
+                    Err(msg) => self.err_msg(value, format!("enum_style: {}", msg), ctxt),
+                },
+                // #[ts(json_style = "unknown")]
+                NameValue(MetaNameValue {
+                    ref path,
+                    lit: Str(ref value),
+                    ..
+                }) if is_path_ident(path, "json_style") => match JsonStyle::parse(&value.value()) {
+                    Ok(style) => self.json_style = Some(style),
+                    Err(msg) => self.err_msg(value, format!("json_style: {}", msg), ctxt),
+                },
+                // #[ts(profile = "wasm_bindgen")]
+                NameValue(MetaNameValue {
+                    ref path,
+                    lit: Str(ref value),
+                    ..
+                }) if is_path_ident(path, "profile") => match Profile::parse(&value.value()) {
+                    Ok(profile) => self.profile = Some(profile),
+                    Err(msg) => self.err_msg(value, format!("profile: {}", msg), ctxt),
+                },
+                // #[ts(strict)]
+                Path(ref path) if is_path_ident(path, "strict") => {
+                    self.strict = Some(true);
+                }
+                // #[ts(strict = false)]
+                NameValue(MetaNameValue {
+                    ref path,
+                    lit: Bool(ref value),
+                    ..
+                }) if is_path_ident(path, "strict") => {
+                    self.strict = Some(value.value);
+                }
+                // #[ts(map_style = "es_map")]
+                NameValue(MetaNameValue {
+                    ref path,
+                    lit: Str(ref value),
+                    ..
+                }) if is_path_ident(path, "map_style") => match MapStyle::parse(&value.value()) {
+                    Ok(style) => self.map_style = Some(style),
+                    Err(msg) => self.err_msg(value, format!("map_style: {}", msg), ctxt),
+                },
+                // #[ts(readonly)]
+                Path(ref path) if is_path_ident(path, "readonly") => {
+                    self.readonly = Some(true);
+                }
+                // #[ts(readonly = false)]
+                NameValue(MetaNameValue {
+                    ref path,
+                    lit: Bool(ref value),
+                    ..
+                }) if is_path_ident(path, "readonly") => {
+                    self.readonly = Some(value.value);
+                }
+                // #[ts(input)]
+                Path(ref path) if is_path_ident(path, "input") => {
+                    self.input = Some(true);
+                }
+                // #[ts(input = false)]
+                NameValue(MetaNameValue {
+                    ref path,
+                    lit: Bool(ref value),
+                    ..
+                }) if is_path_ident(path, "input") => {
+                    self.input = Some(value.value);
+                }
+                // #[ts(tuple_limit = 4)]
+                NameValue(MetaNameValue {
+                    ref path,
+                    lit: Int(ref value),
+                    ..
+                }) if is_path_ident(path, "tuple_limit") => match value.base10_parse() {
+                    Ok(n) => self.tuple_limit = Some(n),
+                    Err(err) => self.err_msg(value, format!("tuple_limit: {}", err), ctxt),
+                },
+                // #[ts(repr)]
+                Path(ref path) if is_path_ident(path, "repr") => {
+                    self.ts_repr = true;
+                }
+                // #[ts(bound = "T: { id: number }")]
+                NameValue(MetaNameValue {
+                    ref path,
+                    lit: Str(ref value),
+                    ..
+                }) if is_path_ident(path, "bound") => {
+                    let v = value.value();
+                    match v.split_once(':') {
+                        Some((param, bound)) if !bound.trim().is_empty() => self
+                            .ts_bounds
+                            .push((param.trim().to_string(), bound.trim().to_string())),
+                        _ => self.err_msg(
+                            value,
+                            format!("bound: \"{}\" is not of the form \"T: Type\"", v),
+                            ctxt,
+                        ),
+                    }
+                }
+                ref i @ NameValue(..) | ref i @ List(..) | ref i @ Path(..) => {
+                    self.err_msg(i, format!("unsupported option: {}", quote!(#i)), ctxt);
+                }
+            }
+        }
+    }
+    pub fn push_field_attrs(
+        &mut self,
+        _struct_ident: &Ident,
+        attrs: &[Attribute],
+        ctxt: Option<&Ctxt>,
+    ) {
+        use syn::Meta::*;
+        use Lit::*;
+        // use NestedMeta::*;
+
+        for attr in Self::find_typescript(attrs, ctxt) {
+            match attr {
+                NameValue(MetaNameValue {
+                    ref path,
+                    lit: Str(ref value),
+                    ..
+                }) if is_path_ident(path, "ts_type") => {
+                    let v = value.value();
+
+                    self.ts_type = Some(v);
+                }
+                NameValue(MetaNameValue {
+                    ref path,
+                    lit: Str(ref value),
+                    ..
+                }) if is_path_ident(path, "ts_as") => {
+                    let v = value.value();
+                    match syn::parse_str::<syn::Type>(&v) {
+                        Ok(t) => self.ts_as = Some(t),
+                        Err(..) => {
+                            self.err_msg(
+                                attr,
+                                format!("ts_as: \"{}\" is not a valid rust type", v),
+                                ctxt,
+                            );
+                        }
+                    }
+                    //
+                }
+                // #[ts(ts_guard = "{ value: number[] }")]
+                NameValue(MetaNameValue {
+                    ref path,
+                    lit: Str(ref value),
+                    ..
+                }) if is_path_ident(path, "ts_guard") => {
+                    self.ts_guard = Some(value.value());
+                }
+                // #[ts(int64 = "string")]
+                NameValue(MetaNameValue {
+                    ref path,
+                    lit: Str(ref value),
+                    ..
+                }) if is_path_ident(path, "int64") => match Int64::parse(&value.value()) {
+                    Ok(int64) => self.int64 = Some(int64),
+                    Err(msg) => self.err_msg(value, format!("int64: {}", msg), ctxt),
+                },
+                // #[ts(optional)]
+                Path(ref path) if is_path_ident(path, "optional") => {
+                    self.ts_optional = true;
+                }
+                // #[ts(map_style = "es_map")]
+                NameValue(MetaNameValue {
+                    ref path,
+                    lit: Str(ref value),
+                    ..
+                }) if is_path_ident(path, "map_style") => match MapStyle::parse(&value.value()) {
+                    Ok(style) => self.map_style = Some(style),
+                    Err(msg) => self.err_msg(value, format!("map_style: {}", msg), ctxt),
+                },
+                // #[ts(readonly)]
+                Path(ref path) if is_path_ident(path, "readonly") => {
+                    self.readonly = Some(true);
+                }
+                // #[ts(readonly = false)]
+                NameValue(MetaNameValue {
+                    ref path,
+                    lit: Bool(ref value),
+                    ..
+                }) if is_path_ident(path, "readonly") => {
+                    self.readonly = Some(value.value);
+                }
+                // #[ts(tuple_limit = 4)]
+                NameValue(MetaNameValue {
+                    ref path,
+                    lit: Int(ref value),
+                    ..
+                }) if is_path_ident(path, "tuple_limit") => match value.base10_parse() {
+                    Ok(n) => self.tuple_limit = Some(n),
+                    Err(err) => self.err_msg(value, format!("tuple_limit: {}", err), ctxt),
+                },
+
+                ref i @ NameValue(..) | ref i @ List(..) | ref i @ Path(..) => {
+                    self.err_msg(i, format!("unsupported option: {}", quote!(#i)), ctxt);
+                }
+            }
+        }
+    }
+
+    pub fn from_variant(variant: &ast::Variant) -> Attrs {
+        let mut res = Self::new();
+        res.push_doc_comment(&variant.original.attrs);
+        res
+    }
+
+    pub fn from_field(field: &ast::Field, ctxt: Option<&Ctxt>) -> Attrs {
+        let mut res = Self::new();
+        if let Some(ref ident) = field.original.ident {
+            res.push_field_attrs(ident, &field.original.attrs, ctxt);
+        } else {
+            let id = ident_from_str("unnamed");
+            res.push_field_attrs(&id, &field.original.attrs, ctxt);
+        }
+        res.push_doc_comment(&field.original.attrs);
+        res
+    }
+}
+
+fn is_path_ident(path: &syn::Path, test: &str) -> bool {
+    if let Some(ref ident) = path.get_ident() {
+        format!("{}", ident) == *test
+    } else {
+        false
+    }
+}
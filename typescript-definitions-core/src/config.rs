@@ -0,0 +1,217 @@
+// Copyright 2019 Ian Castleden
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! # Config
+//!
+//! Crate-wide settings from a `typescript-definitions.toml` next to the `Cargo.toml`
+//! of the crate being compiled (or the crate being scanned by the binary):
+//!
+//! ```toml
+//! [types]
+//! Uuid = "string"
+//! "rust_decimal::Decimal" = "string"
+//!
+//! [defaults]
+//! enum_style = "union"
+//! int64 = "string"
+//! strict = true
+//! readonly = true
+//! input = true
+//! profile = "msgpack"
+//! json_style = "unknown"
+//! option_style = "optional"
+//! map_style = "index"
+//!
+//! [outputs]
+//! zod = false
+//! ```
+
+use super::attrs::{EnumStyle, Int64, JsonStyle, MapStyle, OptionStyle, Profile};
+use lazy_static::lazy_static;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use std::{env, fs};
+
+pub const CONFIG_FILE: &str = "typescript-definitions.toml";
+
+#[derive(Debug, Default)]
+pub struct Config {
+    /// the file this came from (if any)
+    pub path: Option<PathBuf>,
+    /// rust type (last path segment or full path) => typescript type
+    pub types: BTreeMap<String, String>,
+    /// `[defaults]`: what a container without the `#[ts(...)]` attribute gets
+    pub defaults: Defaults,
+    /// `[outputs]`: what the derive macro writes besides the typescript type
+    pub outputs: Outputs,
+}
+
+/// The crate-wide defaults of container attributes.
+#[derive(Debug, Default)]
+pub struct Defaults {
+    pub enum_style: Option<EnumStyle>,
+    /// of fields without `#[ts(int64 = "...")]`
+    pub int64: Option<Int64>,
+    pub strict: Option<bool>,
+    pub readonly: Option<bool>,
+    pub input: Option<bool>,
+    pub profile: Option<Profile>,
+    pub json_style: Option<JsonStyle>,
+    pub option_style: Option<OptionStyle>,
+    pub map_style: Option<MapStyle>,
+}
+
+/// The optional outputs of `#[derive(TypeScriptify)]`. Each is a string
+/// in the binary for every type, so they can be switched off.
+#[derive(Debug)]
+pub struct Outputs {
+    /// `type_script_guard()`
+    pub guard: bool,
+    /// `type_script_zod()`
+    pub zod: bool,
+    /// `json_schema()` and the schemas in the registry
+    pub json_schema: bool,
+}
+
+impl Default for Outputs {
+    fn default() -> Self {
+        Self {
+            guard: true,
+            zod: true,
+            json_schema: true,
+        }
+    }
+}
+
+/// a config and the modification time of its file (`None` if there is no file)
+type Cached = (Option<SystemTime>, Arc<Config>);
+
+lazy_static! {
+    // a proc macro may be kept loaded for more than one crate (e.g. by an IDE) and
+    // outlive edits to the file, so each config is kept with the file's modification time
+    static ref CONFIGS: Mutex<HashMap<PathBuf, Cached>> = Mutex::new(HashMap::new());
+}
+
+impl Config {
+    /// the config for the crate being compiled
+    pub fn load() -> Result<Arc<Config>, String> {
+        let dir = match env::var_os("CARGO_MANIFEST_DIR") {
+            Some(dir) => PathBuf::from(dir),
+            None => env::current_dir().map_err(|err| err.to_string())?,
+        };
+        Config::load_dir(&dir)
+    }
+
+    /// the config in directory `dir` (empty if there is no config file)
+    pub fn load_dir(dir: &Path) -> Result<Arc<Config>, String> {
+        let path = dir.join(CONFIG_FILE);
+        let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+        let mut configs = CONFIGS.lock().unwrap();
+        match configs.get(&path) {
+            Some((cached, config)) if *cached == modified => return Ok(config.clone()),
+            _ => {}
+        }
+        let config = if path.is_file() {
+            let source =
+                fs::read_to_string(&path).map_err(|err| format!("{}: {}", path.display(), err))?;
+            let mut config =
+                Config::parse(&source).map_err(|err| format!("{}: {}", path.display(), err))?;
+            config.path = Some(path.clone());
+            config
+        } else {
+            Config::default()
+        };
+        let config = Arc::new(config);
+        configs.insert(path, (modified, config.clone()));
+        Ok(config)
+    }
+
+    pub fn parse(source: &str) -> Result<Config, String> {
+        let value = source
+            .parse::<toml::Value>()
+            .map_err(|err| err.to_string())?;
+        let mut config = Config::default();
+        if let Some(types) = value.get("types") {
+            let types = types.as_table().ok_or("[types] must be a table")?;
+            for (rust, ts) in types {
+                let ts = ts
+                    .as_str()
+                    .ok_or_else(|| format!("types.{}: must be a typescript type string", rust))?;
+                if ts.parse::<proc_macro2::TokenStream>().is_err() {
+                    return Err(format!("types.{}: can't parse type {}", rust, ts));
+                }
+                let rust = rust.trim_start_matches("::").replace(' ', "");
+                config.types.insert(rust, ts.to_string());
+            }
+        }
+        if let Some(defaults) = value.get("defaults") {
+            let defaults = defaults.as_table().ok_or("[defaults] must be a table")?;
+            for (key, value) in defaults {
+                let err = |msg: String| format!("defaults.{}: {}", key, msg);
+                let string = || value.as_str().ok_or_else(|| err("must be a string".into()));
+                let boolean = || {
+                    value
+                        .as_bool()
+                        .ok_or_else(|| err("must be true or false".into()))
+                };
+                match key.as_str() {
+                    "enum_style" => {
+                        config.defaults.enum_style = Some(EnumStyle::parse(string()?).map_err(err)?)
+                    }
+                    "int64" => config.defaults.int64 = Some(Int64::parse(string()?).map_err(err)?),
+                    "strict" => config.defaults.strict = Some(boolean()?),
+                    "readonly" => config.defaults.readonly = Some(boolean()?),
+                    "input" => config.defaults.input = Some(boolean()?),
+                    "profile" => {
+                        config.defaults.profile = Some(Profile::parse(string()?).map_err(err)?)
+                    }
+                    "json_style" => {
+                        config.defaults.json_style = Some(JsonStyle::parse(string()?).map_err(err)?)
+                    }
+                    "option_style" => {
+                        config.defaults.option_style =
+                            Some(OptionStyle::parse(string()?).map_err(err)?)
+                    }
+                    "map_style" => {
+                        config.defaults.map_style = Some(MapStyle::parse(string()?).map_err(err)?)
+                    }
+                    _ => return Err(err("unknown setting".into())),
+                }
+            }
+        }
+        if let Some(outputs) = value.get("outputs") {
+            let outputs = outputs.as_table().ok_or("[outputs] must be a table")?;
+            for (key, value) in outputs {
+                let on = value
+                    .as_bool()
+                    .ok_or_else(|| format!("outputs.{}: must be true or false", key))?;
+                match key.as_str() {
+                    "guard" => config.outputs.guard = on,
+                    "zod" => config.outputs.zod = on,
+                    "json_schema" => config.outputs.json_schema = on,
+                    _ => return Err(format!("outputs.{}: unknown output", key)),
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    /// the typescript type for a rust type with this `path`: the
+    /// full path is tried before the last segment
+    pub fn lookup(&self, path: &[String]) -> Option<&str> {
+        if self.types.is_empty() {
+            return None;
+        }
+        self.types
+            .get(&path.join("::"))
+            .or_else(|| path.last().and_then(|last| self.types.get(last)))
+            .map(|ts| ts.as_str())
+    }
+}
@@ -0,0 +1,779 @@
+// Copyright 2019 Ian Castleden
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+use super::attrs::{Attrs, EnumStyle};
+use super::guard::{object_guard, tag_guard};
+use super::ts::{
+    self, BinOp, Body, Decl, Docs, EnumMember, Expr, Function, Lit, Member, Param, Prop, Stmt, Type,
+};
+use super::{ParseContext, QuoteMaker, QuoteMakerKind};
+use serde_derive_internals::{ast, ast::Variant, attr::TagType};
+use syn::ext::IdentExt;
+const CONTENT: &str = "fields"; // default content tag
+                                // const TAG: &'static str = "kind"; // default tag tag
+struct TagInfo<'a> {
+    /// #[serde(tag = "...")]
+    tag: Option<&'a str>,
+    /// #[serde(content = "...")]
+    content: Option<&'a str>,
+    /// flattened without tag `{ "key1": "", "key2": "" }`
+    untagged: bool,
+}
+
+impl<'a> TagInfo<'a> {
+    fn from_enum(e: &'a TagType) -> Self {
+        match e {
+            TagType::Internal { tag, .. } => TagInfo {
+                tag: Some(tag),
+                content: None,
+                untagged: false,
+            },
+            TagType::Adjacent { tag, content, .. } => TagInfo {
+                tag: Some(tag),
+                content: Some(content),
+                untagged: false,
+            },
+            TagType::External => TagInfo {
+                tag: None,
+                content: None,
+                untagged: false,
+            },
+            TagType::None => TagInfo {
+                tag: None,
+                content: None,
+                untagged: true,
+            },
+        }
+    }
+}
+
+struct VariantQuoteMaker {
+    /// message type possibly including tag key value
+    pub source: Type,
+    /// inner type
+    pub inner_type: Option<Type>,
+    /// type guard expression checking `obj` is this variant
+    pub guard: Expr,
+}
+
+fn variant_docs(variant: &Variant) -> Docs {
+    Attrs::from_variant(variant).docs()
+}
+
+/// the rust name of a variant (without any `r#`)
+fn rust_name(variant: &Variant) -> String {
+    variant.ident.unraw().to_string()
+}
+
+#[allow(clippy::or_fun_call, clippy::bind_instead_of_map)]
+impl<'a> ParseContext {
+    /// `key: ty` with the variant's doc comment
+    fn property(&self, docs: Docs, key: &str, ty: Type) -> Member {
+        Member::Property {
+            docs,
+            key: key.to_string(),
+            optional: false,
+            readonly: self.readonly(),
+            ty,
+        }
+    }
+
+    pub(crate) fn derive_enum(
+        &self,
+        variants: &[ast::Variant<'a>],
+        ast_container: &ast::Container,
+    ) -> QuoteMaker {
+        // serialized as the discriminant by `serde_repr`
+        if self.repr {
+            return self.derive_repr_enum(variants);
+        }
+
+        // https://serde.rs/enum-representations.html
+        let taginfo = TagInfo::from_enum(ast_container.attrs.tag());
+        // remove skipped ( check for #[serde(skip)] )
+        let variants: Vec<&ast::Variant<'a>> = variants
+            .iter()
+            .filter(|v| !self.is_skipped_variant(v))
+            .collect();
+
+        // is typescript enum compatible
+        let is_enum = taginfo.tag.is_none()
+            && taginfo.content.is_none()
+            && variants.iter().all(|v| matches!(v.style, ast::Style::Unit));
+
+        if is_enum {
+            let v = variants
+                .iter()
+                .map(|v| self.serde_name(v.attrs.name())) // use serde name instead of v.ident
+                .collect::<Vec<_>>();
+            let k = v.clone();
+            let v = v.into_iter().map(Lit::Str).collect::<Vec<_>>();
+            return self.derive_unit_enum(&variants, &k, &v);
+        }
+
+        let content: Vec<(&Variant, VariantQuoteMaker)> = variants
+            .iter()
+            .map(|variant| {
+                (
+                    *variant,
+                    match variant.style {
+                        ast::Style::Struct => self.derive_struct_variant(
+                            &taginfo,
+                            variant,
+                            &variant.fields,
+                            ast_container,
+                        ),
+                        ast::Style::Newtype => {
+                            self.derive_newtype_variant(&taginfo, variant, &variant.fields[0])
+                        }
+                        ast::Style::Tuple => {
+                            self.derive_tuple_variant(&taginfo, variant, &variant.fields)
+                        }
+                        ast::Style::Unit => self.derive_unit_variant(&taginfo, variant),
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let enum_factory = taginfo
+            .tag
+            .ok_or("serde tag must be specified to create enum factory")
+            .map(|tag_key| self.enum_factory(&taginfo, tag_key, &content));
+
+        let enum_handler = taginfo
+            .tag
+            .ok_or("serde tag must be specified to create enum handler")
+            .map(|tag_key| self.enum_handler(&taginfo, tag_key, &content));
+
+        // OK generate A | B | C etc
+        let sources = content
+            .iter()
+            .map(|(_, q)| q.source.clone())
+            .collect::<Vec<_>>();
+        QuoteMaker {
+            zod: self.variants_to_zod(taginfo.tag, &sources),
+            source: Type::union(sources),
+            guard: Expr::or(content.iter().map(|(_, q)| q.guard.clone()).collect()),
+            enum_factory,
+            enum_handler,
+            kind: QuoteMakerKind::Union,
+        }
+    }
+
+    /// `export const Foo = Object.freeze({ A(content): Foo { return ... } })` and
+    /// `FooFactory`, which passes each message to a callback
+    fn enum_factory(
+        &self,
+        taginfo: &TagInfo,
+        tag_key: &str,
+        content: &[(&Variant, VariantQuoteMaker)],
+    ) -> Vec<Decl> {
+        let params = |q: &VariantQuoteMaker| {
+            q.inner_type
+                .iter()
+                .map(|inner_type| Param::new("content", inner_type.clone()))
+                .collect::<Vec<_>>()
+        };
+        // the message `{ tag: "A", content: content }`
+        let construct = |v: &Variant, q: &VariantQuoteMaker| {
+            let mut props = vec![Prop::Value {
+                docs: vec![],
+                key: tag_key.to_string(),
+                value: Expr::str(self.variant_name(v)),
+            }];
+            if q.inner_type.is_some() {
+                props.push(match taginfo.content {
+                    Some(content_key) => Prop::Value {
+                        docs: vec![],
+                        key: content_key.to_string(),
+                        value: Expr::ident("content"),
+                    },
+                    None => Prop::Spread(Expr::ident("content")),
+                });
+            }
+            Expr::Object(props)
+        };
+        let method = |v: &Variant, q: &VariantQuoteMaker, type_params, ret, body| Prop::Method {
+            name: rust_name(v),
+            function: Function {
+                type_params,
+                params: params(q),
+                ret: Some(ret),
+                body: Body::Block(vec![Stmt::Return(body)]),
+            },
+        };
+        let freeze = |props| Expr::ident("Object").method("freeze", vec![Expr::Object(props)]);
+
+        let type_ident = self.ident.to_string();
+        let factory_ident = self
+            .global_attrs
+            .ts_factory_name
+            .clone()
+            // default naming
+            .unwrap_or(format!("{}Factory", type_ident));
+        let factory_type_ident = self
+            .global_attrs
+            .ts_factory_return_name
+            .clone()
+            // default naming
+            .unwrap_or(format!("{}ReturnType", factory_ident));
+
+        // generic enums: `Foo<T>`
+        let self_type = Type::Ref(type_ident.clone(), self.generics_args());
+        let r = Type::named("R");
+        let mut factory_type_args = self.generics_args();
+        factory_type_args.push(r.clone());
+        let mut factory_type_params = vec![ts::TypeParam::new("R")];
+        factory_type_params.extend(self.generics_decl());
+        let mut return_type_params = self.generics_decl();
+        return_type_params.push(ts::TypeParam {
+            default: Some(Type::named("void")),
+            ..ts::TypeParam::new("R")
+        });
+
+        let constructors = content
+            .iter()
+            .map(|(v, q)| {
+                method(
+                    v,
+                    q,
+                    self.generics_decl(),
+                    self_type.clone(),
+                    construct(v, q),
+                )
+            })
+            .collect();
+        let callbacks = content
+            .iter()
+            .map(|(v, q)| {
+                let message = Expr::ident("fn").call(vec![construct(v, q)]);
+                method(v, q, vec![], r.clone(), message)
+            })
+            .collect();
+        let message = Param::new("message", self_type.clone());
+        vec![
+            Decl::Const {
+                docs: self.global_attrs.docs(),
+                name: type_ident,
+                ty: None,
+                value: freeze(constructors),
+            },
+            Decl::Const {
+                docs: vec![],
+                name: factory_ident,
+                ty: None,
+                value: Expr::Arrow(Box::new(Function {
+                    type_params: factory_type_params,
+                    params: vec![Param::new(
+                        "fn",
+                        Type::Function(vec![message], Box::new(r.clone())),
+                    )],
+                    ret: Some(Type::Ref(factory_type_ident.clone(), factory_type_args)),
+                    body: Body::Expr(freeze(callbacks)),
+                })),
+            },
+            Decl::Type {
+                docs: vec![],
+                export: true,
+                name: factory_type_ident,
+                type_params: return_type_params,
+                ty: Type::Object(
+                    content
+                        .iter()
+                        .map(|(v, q)| Member::Method {
+                            name: rust_name(v),
+                            type_params: vec![],
+                            params: params(q),
+                            ret: r.clone(),
+                        })
+                        .collect(),
+                ),
+            },
+        ]
+    }
+
+    /// `interface HandleFoo { onA(message): R }`, a type for each variant's content
+    /// and `applyFoo`, which calls the handler for a message
+    fn enum_handler(
+        &self,
+        taginfo: &TagInfo,
+        tag_key: &str,
+        content: &[(&Variant, VariantQuoteMaker)],
+    ) -> Vec<Decl> {
+        // generic enums: `Foo<T>`
+        let generics = self.generics_decl();
+        let mut conflict_aliases = vec![];
+        let mut variant_types = vec![];
+        for (v, q) in content {
+            if let Some(ref inner_type) = q.inner_type {
+                let variant_ident = rust_name(v);
+                let ty = if variant_ident == inner_type.to_string() {
+                    // `type A = A` would refer to itself
+                    let alias = format!("_{}", variant_ident);
+                    conflict_aliases.push(Decl::Type {
+                        docs: vec![],
+                        export: false,
+                        name: alias.clone(),
+                        type_params: generics.clone(),
+                        ty: inner_type.clone(),
+                    });
+                    Type::Ref(alias, self.generics_args())
+                } else {
+                    inner_type.clone()
+                };
+                variant_types.push(Decl::Type {
+                    docs: vec![],
+                    export: true,
+                    // `type delete = ..` isn't allowed
+                    name: if ts::is_ident(&variant_ident) {
+                        variant_ident
+                    } else {
+                        format!("_{}", variant_ident)
+                    },
+                    type_params: generics.clone(),
+                    ty,
+                });
+            }
+        }
+        let ret_type = Type::from_source(
+            self.global_attrs
+                .ts_handler_return
+                .as_deref()
+                // default return type to any
+                .unwrap_or("any"),
+        );
+
+        let type_ident = self.ident.to_string();
+        let interface_ident = self
+            .global_attrs
+            .ts_handler_name
+            .clone()
+            // default naming
+            .unwrap_or(format!("Handle{}", type_ident));
+
+        let input = Expr::ident("input");
+        let content_value = match taginfo.content {
+            Some(content_key) => input.member(content_key),
+            None => input.clone(),
+        };
+        let handlers = content
+            .iter()
+            .map(|(v, _)| (self.variant_name(v), self.handler_name(v)))
+            .collect::<Vec<_>>();
+        let on_tag = if handlers
+            .iter()
+            .all(|(name, on)| *on == format!("on{}", name))
+        {
+            // "on" + input.tag
+            Expr::Binary(
+                Box::new(Expr::str("on")),
+                BinOp::Add,
+                Box::new(input.member(tag_key)),
+            )
+        } else {
+            // { "a-b": "onAB", ... }[input.tag]
+            Expr::Object(
+                handlers
+                    .iter()
+                    .map(|(name, on)| Prop::Value {
+                        docs: vec![],
+                        key: name.clone(),
+                        value: Expr::str(on.as_str()),
+                    })
+                    .collect(),
+            )
+            .index(input.member(tag_key))
+        };
+        // handler[on_tag](input.content)
+        let apply = Expr::ident("handler")
+            .index(on_tag)
+            .call(vec![content_value]);
+
+        let mut decls = vec![Decl::Interface {
+            docs: self.global_attrs.docs(),
+            name: interface_ident.clone(),
+            type_params: generics.clone(),
+            members: content
+                .iter()
+                .map(|(v, q)| Member::Method {
+                    name: self.handler_name(v),
+                    type_params: vec![],
+                    params: q
+                        .inner_type
+                        .iter()
+                        .map(|inner_type| Param::new("message", inner_type.clone()))
+                        .collect(),
+                    ret: ret_type.clone(),
+                })
+                .collect(),
+        }];
+        decls.extend(conflict_aliases);
+        decls.push(Decl::Namespace {
+            name: type_ident.clone(),
+            decls: variant_types,
+        });
+        decls.push(Decl::Function {
+            docs: vec![],
+            name: format!("apply{}", type_ident),
+            function: Function {
+                type_params: generics,
+                params: vec![Param::new(
+                    "handler",
+                    Type::Ref(interface_ident, self.generics_args()),
+                )],
+                ret: Some(Type::Function(
+                    vec![Param::new(
+                        "input",
+                        Type::Ref(type_ident, self.generics_args()),
+                    )],
+                    Box::new(ret_type),
+                )),
+                body: Body::Block(vec![
+                    Stmt::Comment("@ts-ignore".into()),
+                    Stmt::Return(Expr::arrow(vec![Param::untyped("input")], None, apply)),
+                ]),
+            },
+        });
+        decls
+    }
+
+    /// an enum of unit variants named `k` with values `v`, written in our `enum_style`
+    fn derive_unit_enum(
+        &self,
+        variants: &[&ast::Variant<'a>],
+        k: &[String],
+        v: &[Lit],
+    ) -> QuoteMaker {
+        let obj = Expr::ident("obj");
+        let guard = Expr::or(v.iter().map(|v| obj.eq3(Expr::Lit(v.clone()))).collect());
+        let union = Type::union(v.iter().cloned().map(Type::Lit).collect());
+        let members = || {
+            variants
+                .iter()
+                .zip(k.iter().zip(v))
+                .map(|(variant, (k, v))| EnumMember {
+                    docs: variant_docs(variant),
+                    name: k.clone(),
+                    value: v.clone(),
+                })
+                .collect::<Vec<_>>()
+        };
+        let kind = match self.enum_style() {
+            EnumStyle::Enum => QuoteMakerKind::Enum(members()),
+            EnumStyle::ConstEnum => QuoteMakerKind::ConstEnum(members()),
+            EnumStyle::Union => QuoteMakerKind::Object,
+            EnumStyle::ConstObject => QuoteMakerKind::ConstObject(Expr::Object(
+                members()
+                    .into_iter()
+                    .map(|m| Prop::Value {
+                        docs: m.docs,
+                        key: m.name,
+                        value: Expr::Lit(m.value),
+                    })
+                    .collect(),
+            )),
+        };
+
+        QuoteMaker {
+            zod: self.unit_enum_to_zod(&kind, &union),
+            source: union,
+            guard,
+            enum_factory: Err("factory cannot be created with raw enum type"),
+            enum_handler: Err("handler cannot be created with raw enum type"),
+            kind,
+        }
+    }
+
+    /// `#[repr(u8)] enum Color { Red = 1, Green }` => `enum Color { Red = 1, Green = 2 }`
+    fn derive_repr_enum(&self, variants: &[ast::Variant<'a>]) -> QuoteMaker {
+        if let Some(v) = variants
+            .iter()
+            .find(|v| !matches!(v.style, ast::Style::Unit))
+        {
+            self.err_msg(
+                v.original,
+                &format!("{}: a repr enum can only have unit variants", self.ident),
+            );
+        }
+        // discriminants follow on from the previous one (starting at 0) unless given
+        let mut discriminant: i128 = 0;
+        let mut values = vec![];
+        for v in variants {
+            if let Some((_, ref expr)) = v.original.discriminant {
+                match int_value(expr) {
+                    Some(d) => discriminant = d,
+                    None => self.err_msg(
+                        expr,
+                        &format!(
+                            "{}: can't evaluate the discriminant of {}",
+                            self.ident, v.ident
+                        ),
+                    ),
+                }
+            }
+            values.push(Lit::Num(discriminant.to_string()));
+            discriminant += 1;
+        }
+        let (variants, values): (Vec<_>, Vec<_>) = variants
+            .iter()
+            .zip(values)
+            .filter(|(v, _)| !self.is_skipped_variant(v))
+            .unzip();
+        let k = variants.iter().map(|v| rust_name(v)).collect::<Vec<_>>();
+        self.derive_unit_enum(&variants, &k, &values)
+    }
+
+    /// Depends on TagInfo for layout
+    fn derive_unit_variant(&self, taginfo: &TagInfo, variant: &Variant) -> VariantQuoteMaker {
+        let variant_name = self.variant_name(variant);
+        let obj = Expr::ident("obj");
+        if taginfo.tag.is_none() {
+            return VariantQuoteMaker {
+                source: Type::str(variant_name.clone()),
+                inner_type: None,
+                guard: obj.eq3(Expr::str(variant_name)),
+            };
+        }
+        let tag = taginfo.tag.unwrap();
+        VariantQuoteMaker {
+            source: Type::Object(vec![self.property(
+                variant_docs(variant),
+                tag,
+                Type::str(variant_name.clone()),
+            )]),
+            inner_type: None,
+            guard: Expr::and(vec![
+                object_guard(&obj),
+                tag_guard(&obj, tag, &variant_name),
+            ]),
+        }
+    }
+
+    /// Depends on TagInfo for layout
+    /// example variant: `C(u32)`
+    fn derive_newtype_variant(
+        &self,
+        taginfo: &TagInfo,
+        variant: &Variant,
+        field: &ast::Field<'a>,
+    ) -> VariantQuoteMaker {
+        if self.is_skipped_field(field) {
+            return self.derive_unit_variant(taginfo, variant);
+        };
+        let docs = variant_docs(variant);
+        let ty = self.field_to_ts(field);
+        let variant_name = self.variant_name(variant);
+        let obj = Expr::ident("obj");
+        let is_obj = object_guard(&obj);
+
+        if taginfo.tag.is_none() {
+            if taginfo.untagged {
+                return VariantQuoteMaker {
+                    source: ty.clone(),
+                    inner_type: Some(ty),
+                    guard: self.field_to_guard(field, &obj),
+                };
+            };
+            let guard = self.field_to_guard(field, &obj.member(variant_name.as_str()));
+
+            return VariantQuoteMaker {
+                source: Type::Object(vec![self.property(docs, &variant_name, ty.clone())]),
+                inner_type: Some(ty),
+                guard: Expr::and(vec![is_obj, obj.has(&variant_name), guard]),
+            };
+        };
+        let tag = taginfo.tag.unwrap();
+        let is_tag = tag_guard(&obj, tag, &variant_name);
+        let tagged = self.property(docs, tag, Type::str(variant_name));
+
+        match taginfo.content {
+            Some(content) => {
+                let guard = self.field_to_guard(field, &obj.member(content));
+                VariantQuoteMaker {
+                    source: Type::Object(vec![tagged, self.property(vec![], content, ty.clone())]),
+                    inner_type: Some(ty),
+                    guard: Expr::and(vec![is_obj, is_tag, guard]),
+                }
+            }
+            // internally tagged: serde writes the tag next to the inner struct's fields
+            None => {
+                let guard = self.field_to_guard(field, &obj);
+                VariantQuoteMaker {
+                    source: Type::intersection(vec![Type::Object(vec![tagged]), ty.clone()]),
+                    inner_type: Some(ty),
+                    guard: Expr::and(vec![is_obj, is_tag, guard]),
+                }
+            }
+        }
+    }
+
+    /// Depends on TagInfo for layout
+    /// `C { a: u32, b: u32 }` => `C: { a: number, b: number }`
+    fn derive_struct_variant(
+        &self,
+        taginfo: &TagInfo,
+        variant: &Variant,
+        fields: &[ast::Field<'a>],
+        ast_container: &ast::Container,
+    ) -> VariantQuoteMaker {
+        use std::collections::HashSet;
+        let fields = self.filter_visible(fields);
+        if fields.is_empty() {
+            return self.derive_unit_variant(taginfo, variant);
+        }
+
+        let docs = variant_docs(variant);
+        let variant_name = self.variant_name(variant);
+
+        let ty = self.derive_object(&fields);
+        let obj = Expr::ident("obj");
+        let is_obj = object_guard(&obj);
+
+        if taginfo.tag.is_none() {
+            if taginfo.untagged {
+                let mut guards = vec![is_obj];
+                guards.extend(self.derive_fields_guard(&fields, &obj));
+                return VariantQuoteMaker {
+                    source: ty.clone(),
+                    inner_type: Some(ty),
+                    guard: Expr::and(guards),
+                };
+            };
+            let inner = obj.member(variant_name.as_str());
+            let mut guards = vec![is_obj, obj.has(&variant_name), object_guard(&inner)];
+            guards.extend(self.derive_fields_guard(&fields, &inner));
+            return VariantQuoteMaker {
+                source: Type::Object(vec![self.property(docs, &variant_name, ty.clone())]),
+                inner_type: Some(ty),
+                guard: Expr::and(guards),
+            };
+        }
+        let tag = taginfo.tag.unwrap();
+        let is_tag = tag_guard(&obj, tag, &variant_name);
+
+        if let Some(content) = taginfo.content {
+            let inner = obj.member(content);
+            let mut guards = vec![is_obj, is_tag, object_guard(&inner)];
+            guards.extend(self.derive_fields_guard(&fields, &inner));
+
+            VariantQuoteMaker {
+                source: Type::Object(vec![
+                    self.property(docs, tag, Type::str(variant_name)),
+                    self.property(vec![], content, ty.clone()),
+                ]),
+                inner_type: Some(ty),
+                guard: Expr::and(guards),
+            }
+        } else {
+            if let Some(ref cx) = self.ctxt {
+                let fnames = fields
+                    .iter()
+                    .map(|field| self.serde_name(field.attrs.name()))
+                    .collect::<HashSet<_>>();
+                if fnames.contains(tag) {
+                    cx.error_spanned_by(
+                        tag,
+                        format!(
+                            "clash with field in \"{}::{}\". \
+                         Maybe use a #[serde(content=\"...\")] attribute.",
+                            ast_container.ident, variant_name
+                        ),
+                    );
+                }
+            };
+            // spread together tagged no content
+            let mut members = vec![self.property(docs, tag, Type::str(variant_name))];
+            members.extend(self.derive_fields(&fields));
+            let mut source = vec![Type::Object(members)];
+            source.extend(self.derive_flatten(&fields));
+            let mut guards = vec![is_obj, is_tag];
+            guards.extend(self.derive_fields_guard(&fields, &obj));
+            VariantQuoteMaker {
+                source: Type::intersection(source),
+                inner_type: Some(ty),
+                guard: Expr::and(guards),
+            }
+        }
+    }
+
+    #[inline]
+    fn variant_name(&self, variant: &Variant) -> String {
+        self.serde_name(variant.attrs.name()) // use serde name instead of variant.ident
+    }
+
+    /// `on{name}`, or `on{Variant}` when the serde name doesn't make an identifier
+    fn handler_name(&self, variant: &Variant) -> String {
+        let on = format!("on{}", self.variant_name(variant));
+        if ts::is_ident(&on) {
+            on
+        } else {
+            format!("on{}", rust_name(variant))
+        }
+    }
+
+    /// `B(u32, u32)` => `B: [number, number]`
+    fn derive_tuple_variant(
+        &self,
+        taginfo: &TagInfo,
+        variant: &Variant,
+        fields: &[ast::Field<'a>],
+    ) -> VariantQuoteMaker {
+        let variant_name = self.variant_name(variant);
+        let fields = self.filter_visible(fields);
+        let docs = variant_docs(variant);
+        let ty = Type::Tuple(self.derive_field_tuple(&fields));
+        let obj = Expr::ident("obj");
+        let is_obj = object_guard(&obj);
+
+        if taginfo.tag.is_none() {
+            if taginfo.untagged {
+                return VariantQuoteMaker {
+                    source: ty.clone(),
+                    inner_type: Some(ty),
+                    guard: self.derive_field_tuple_guard(&fields, &obj),
+                };
+            }
+            let guard = self.derive_field_tuple_guard(&fields, &obj.member(variant_name.as_str()));
+            return VariantQuoteMaker {
+                source: Type::Object(vec![self.property(docs, &variant_name, ty.clone())]),
+                inner_type: Some(ty),
+                guard: Expr::and(vec![is_obj, obj.has(&variant_name), guard]),
+            };
+        };
+
+        let tag = taginfo.tag.unwrap();
+        let content = taginfo.content.unwrap_or(CONTENT);
+        let is_tag = tag_guard(&obj, tag, &variant_name);
+        let guard = self.derive_field_tuple_guard(&fields, &obj.member(content));
+
+        VariantQuoteMaker {
+            source: Type::Object(vec![
+                self.property(docs, tag, Type::str(variant_name)),
+                self.property(vec![], content, ty.clone()),
+            ]),
+            inner_type: Some(ty),
+            guard: Expr::and(vec![is_obj, is_tag, guard]),
+        }
+    }
+}
+
+/// the value of an integer literal discriminant `1`, `-1` or `0x10`
+fn int_value(expr: &syn::Expr) -> Option<i128> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(i),
+            ..
+        }) => i.base10_parse().ok(),
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(..),
+            expr,
+            ..
+        }) => int_value(expr).map(|i: i128| -i),
+        syn::Expr::Paren(syn::ExprParen { expr, .. }) => int_value(expr),
+        _ => None,
+    }
+}
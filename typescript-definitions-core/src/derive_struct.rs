@@ -0,0 +1,134 @@
+// Copyright 2019 Ian Castleden
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use serde_derive_internals::ast;
+
+use super::attrs::Profile;
+use super::guard::{object_guard, tuple_guard};
+use super::ts::{Decl, Expr, Type};
+use super::{ParseContext, QuoteMaker, QuoteMakerKind};
+
+const DEFAULT_ERROR: Result<Vec<Decl>, &'static str> =
+    Err("struct cannot have a handler or factory");
+
+impl<'a> ParseContext {
+    pub(crate) fn derive_struct(
+        &self,
+        style: ast::Style,
+        fields: &[ast::Field<'a>],
+        container: &ast::Container,
+    ) -> QuoteMaker {
+        match style {
+            ast::Style::Struct => self.derive_struct_named_fields(fields, container),
+            ast::Style::Newtype => self.derive_struct_newtype(&fields[0]),
+            ast::Style::Tuple => self.derive_struct_tuple(fields, container),
+            ast::Style::Unit => self.derive_unit_struct(),
+        }
+    }
+
+    fn derive_struct_newtype(&self, field: &ast::Field<'a>) -> QuoteMaker {
+        if self.is_skipped_field(field) {
+            return self.derive_struct_unit();
+        }
+
+        let source = self.field_to_ts(field);
+        QuoteMaker {
+            zod: self.type_to_zod(&source),
+            source,
+            guard: self.field_to_guard(field, &Expr::ident("obj")),
+            enum_factory: DEFAULT_ERROR,
+            enum_handler: DEFAULT_ERROR,
+            kind: QuoteMakerKind::Object,
+        }
+    }
+
+    /// `struct S;` is `null` in JSON, `undefined` with serde-wasm-bindgen and
+    /// an empty array with rmp-serde
+    fn derive_unit_struct(&self) -> QuoteMaker {
+        let obj = Expr::ident("obj");
+        let (source, guard) = match self.profile() {
+            Profile::Json => (Type::named("null"), obj.eq3(Expr::ident("null"))),
+            Profile::WasmBindgen => (Type::named("undefined"), obj.eq3(Expr::ident("undefined"))),
+            Profile::MessagePack => (
+                Type::Tuple(vec![]),
+                tuple_guard(&obj, 0, std::iter::empty()),
+            ),
+        };
+        QuoteMaker {
+            zod: self.type_to_zod(&source),
+            source,
+            guard,
+            enum_factory: DEFAULT_ERROR,
+            enum_handler: DEFAULT_ERROR,
+            kind: QuoteMakerKind::Object,
+        }
+    }
+
+    fn derive_struct_unit(&self) -> QuoteMaker {
+        QuoteMaker {
+            source: Type::Object(vec![]),
+            guard: object_guard(&Expr::ident("obj")),
+            zod: self.type_to_zod(&Type::Object(vec![])),
+            enum_factory: DEFAULT_ERROR,
+            enum_handler: DEFAULT_ERROR,
+            kind: QuoteMakerKind::Object,
+        }
+    }
+
+    fn derive_struct_named_fields(
+        &self,
+        fields: &[ast::Field<'a>],
+        ast_container: &ast::Container,
+    ) -> QuoteMaker {
+        let fields = self.filter_visible(fields);
+        if fields.is_empty() {
+            return self.derive_struct_unit();
+        };
+
+        if fields.len() == 1 && ast_container.attrs.transparent() {
+            return self.derive_struct_newtype(fields[0]);
+        };
+        let obj = Expr::ident("obj");
+        let mut guards = vec![object_guard(&obj)];
+        guards.extend(self.derive_fields_guard(&fields, &obj));
+
+        let source = self.derive_object(&fields);
+        QuoteMaker {
+            zod: self.type_to_zod(&source),
+            source,
+            guard: Expr::and(guards),
+            enum_factory: DEFAULT_ERROR,
+            enum_handler: DEFAULT_ERROR,
+            kind: QuoteMakerKind::Object,
+        }
+    }
+
+    fn derive_struct_tuple(
+        &self,
+        fields: &[ast::Field<'a>],
+        ast_container: &ast::Container,
+    ) -> QuoteMaker {
+        let fields = self.filter_visible(fields);
+        if fields.is_empty() {
+            return self.derive_struct_unit();
+        }
+
+        if fields.len() == 1 && ast_container.attrs.transparent() {
+            return self.derive_struct_newtype(fields[0]);
+        };
+        let source = Type::Tuple(self.derive_field_tuple(&fields));
+        QuoteMaker {
+            zod: self.type_to_zod(&source),
+            source,
+            guard: self.derive_field_tuple_guard(&fields, &Expr::ident("obj")),
+            enum_factory: DEFAULT_ERROR,
+            enum_handler: DEFAULT_ERROR,
+            kind: QuoteMakerKind::Object,
+        }
+    }
+}
@@ -0,0 +1,580 @@
+// Copyright 2019 Ian Castleden
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! # Guard
+//!
+//! Generates the body of a typescript [type guard](https://www.typescriptlang.org/docs/handbook/advanced-types.html)
+//! `isFoo(obj: any): obj is Foo`. This walks the same rust type tree as `tots.rs`
+//! but instead of a type it produces a javascript boolean expression that
+//! checks the object `obj`.
+
+use super::attrs::{Int64, MapStyle, OptionStyle};
+use super::ts::{Expr, Lit, Member, Param, Type};
+use super::{
+    ast, ecosystem_type, is_byte_buffer, is_bytes, is_int64, is_json_map, is_json_value,
+    last_path_element, option_type, FieldContext, ParseContext, TSType, JSON_VALUE,
+};
+
+/// `typeof obj === "object" && obj !== null`
+pub fn object_guard(obj: &Expr) -> Expr {
+    Expr::and(vec![
+        obj.typeof_is("object"),
+        obj.not_eq3(Expr::ident("null")),
+    ])
+}
+
+/// `obj.tag === "Variant"`
+pub fn tag_guard(obj: &Expr, tag: &str, value: &str) -> Expr {
+    obj.member(tag).eq3(Expr::str(value))
+}
+
+/// the guard function that checks a (possibly generic) type `Foo` is called `isFoo`
+pub fn guard_ident(ident: &str) -> String {
+    format!("is{}", ident)
+}
+
+fn var(prefix: &str, depth: usize) -> Expr {
+    Expr::ident(format!("{}{}", prefix, depth))
+}
+
+/// `(e0: any): e0 is T => guard`: the guard for a type argument of a generic guard
+fn arg_guard(ty: Type, depth: usize, guard: impl FnOnce(&Expr) -> Expr) -> Expr {
+    let e = var("e", depth);
+    let name = e.to_string();
+    Expr::arrow(
+        vec![Param::new(name.clone(), Type::named("any"))],
+        Some(Type::Predicate(name, Box::new(ty))),
+        guard(&e),
+    )
+}
+
+/// `Array.isArray(obj) && obj.every((e0: any) => guard)`
+fn array_guard(obj: &Expr, depth: usize, guard: impl FnOnce(&Expr) -> Expr) -> Expr {
+    Expr::and(vec![
+        Expr::ident("Array").method("isArray", vec![obj.clone()]),
+        every_guard(obj, depth, guard),
+    ])
+}
+
+/// `obj.every((e0: any) => guard)`
+fn every_guard(obj: &Expr, depth: usize, guard: impl FnOnce(&Expr) -> Expr) -> Expr {
+    let e = var("e", depth);
+    let every = Expr::arrow(
+        vec![Param::new(e.to_string(), Type::named("any"))],
+        None,
+        guard(&e),
+    );
+    obj.method("every", vec![every])
+}
+
+/// `Array.isArray(obj) && obj.length === 2 && guard(obj[0]) && guard(obj[1])`
+pub fn tuple_guard(obj: &Expr, len: usize, elems: impl Iterator<Item = Expr>) -> Expr {
+    let mut v = vec![
+        Expr::ident("Array").method("isArray", vec![obj.clone()]),
+        obj.member("length").eq3(Expr::num(len)),
+    ];
+    v.extend(elems);
+    Expr::and(v)
+}
+
+/// `Object.values(obj).every((v0) => guard)`
+fn values_guard(obj: &Expr, depth: usize, guard: impl FnOnce(&Expr) -> Expr) -> Expr {
+    let v = var("v", depth);
+    let every = Expr::arrow(vec![Param::untyped(v.to_string())], None, guard(&v));
+    Expr::ident("Object")
+        .method("values", vec![obj.clone()])
+        .method("every", vec![every])
+}
+
+/// `Object.entries(obj).every(([k0, v0]) => guard)`
+fn entries_guard(obj: &Expr, depth: usize, guard: impl FnOnce(&Expr, &Expr) -> Expr) -> Expr {
+    let entries = Expr::ident("Object").method("entries", vec![obj.clone()]);
+    every_entry(entries, depth, guard)
+}
+
+/// `obj instanceof Map && Array.from(obj).every(([k0, v0]) => guard)`
+fn es_map_guard(obj: &Expr, depth: usize, guard: impl FnOnce(&Expr, &Expr) -> Expr) -> Expr {
+    let entries = Expr::ident("Array").method("from", vec![obj.clone()]);
+    Expr::and(vec![
+        obj.instance_of("Map"),
+        every_entry(entries, depth, guard),
+    ])
+}
+
+/// `entries.every(([k0, v0]) => guard)`
+fn every_entry(entries: Expr, depth: usize, guard: impl FnOnce(&Expr, &Expr) -> Expr) -> Expr {
+    let (k, v) = (var("k", depth), var("v", depth));
+    let every = Expr::arrow(
+        vec![Param::untyped(format!("[{}, {}]", k, v))],
+        None,
+        guard(&k, &v),
+    );
+    entries.method("every", vec![every])
+}
+
+fn is_number(name: &str) -> bool {
+    matches!(
+        name,
+        "u8" | "u16"
+            | "u32"
+            | "u64"
+            | "u128"
+            | "usize"
+            | "i8"
+            | "i16"
+            | "i32"
+            | "i64"
+            | "i128"
+            | "isize"
+            | "f64"
+            | "f32"
+    )
+}
+
+fn is_string(name: &str) -> bool {
+    matches!(name, "String" | "str" | "char" | "Path" | "PathBuf")
+}
+
+impl<'a> FieldContext<'a> {
+    fn generic_to_guard(&self, ts: &TSType, obj: &Expr, depth: usize) -> Expr {
+        let to_guard = |ty: &syn::Type| self.type_to_guard(ty, obj, depth);
+        if let Some(ts_type) = self.ctxt.config.lookup(&ts.path()) {
+            if ts_type == JSON_VALUE {
+                return obj.not_eq3(Expr::ident("undefined"));
+            }
+            // as for `#[ts(ts_type = "...")]`
+            return match Type::parse(ts_type) {
+                Ok(ty) => type_guard(&|n| self.ctxt.is_type_param(n), &ty, obj, depth),
+                Err(..) => Expr::Lit(Lit::Bool(true)),
+            };
+        }
+        if is_byte_buffer(ts) {
+            if self.ctxt.binary_bytes() {
+                return obj.instance_of("Uint8Array");
+            }
+            return to_guard(&syn::parse_quote!(Vec<u8>));
+        }
+        if let Some(ty) = ecosystem_type(ts) {
+            return to_guard(&ty);
+        }
+        let name = ts.ident.to_string();
+        match name.as_ref() {
+            // anything JSON.parse returns
+            _ if is_json_value(ts) => obj.not_eq3(Expr::ident("undefined")),
+            _ if is_json_map(ts) => object_guard(obj),
+            n if is_int64(n) => match self.int64() {
+                Int64::Number => obj.typeof_is("number"),
+                Int64::Bigint => obj.typeof_is("bigint"),
+                Int64::String => obj.typeof_is("string"),
+            },
+            n if is_number(n) => obj.typeof_is("number"),
+            n if is_string(n) => obj.typeof_is("string"),
+            "bool" => obj.typeof_is("boolean"),
+            "Box" | "Cow" | "Rc" | "Arc" | "Cell" | "RefCell" if ts.args.len() == 1 => {
+                to_guard(&ts.args[0])
+            }
+            "Duration" => Expr::and(vec![
+                object_guard(obj),
+                obj.member("secs").typeof_is("number"),
+                obj.member("nanos").typeof_is("number"),
+            ]),
+            "SystemTime" => Expr::and(vec![
+                object_guard(obj),
+                obj.member("secs_since_epoch").typeof_is("number"),
+                obj.member("nanos_since_epoch").typeof_is("number"),
+            ]),
+            // std::collections
+            "Vec" | "VecDeque" | "LinkedList" | "HashSet" | "BTreeSet" if ts.args.len() == 1 => {
+                self.array_to_guard(&ts.args[0], obj, depth)
+            }
+            "HashMap" | "BTreeMap" if ts.args.len() == 2 && self.map_style() == MapStyle::EsMap => {
+                es_map_guard(obj, depth, |k, v| {
+                    Expr::and(vec![
+                        self.type_to_guard(&ts.args[0], k, depth + 1),
+                        self.type_to_guard(&ts.args[1], v, depth + 1),
+                    ])
+                })
+            }
+            "HashMap" | "BTreeMap" if ts.args.len() == 2 => {
+                let (k, v) = (var("k", depth), var("v", depth));
+                let vg = self.type_to_guard(&ts.args[1], &v, depth + 1);
+                let entries = match self.key_to_guard(&ts.args[0], &k, depth + 1) {
+                    Some(kg) => entries_guard(obj, depth, |_, _| Expr::and(vec![kg, vg])),
+                    None => values_guard(obj, depth, |_| vg),
+                };
+                Expr::and(vec![object_guard(obj), entries])
+            }
+            "Option" if ts.args.len() == 1 => {
+                let none = match self.ctxt.option_style() {
+                    OptionStyle::Undefined => "undefined",
+                    _ => "null",
+                };
+                Expr::or(vec![obj.eq3(Expr::ident(none)), to_guard(&ts.args[0])])
+            }
+            "Result" if ts.args.len() == 2 => {
+                self.either_to_guard(("Ok", &ts.args[0]), ("Err", &ts.args[1]), obj, depth)
+            }
+            "Either" if ts.args.len() == 2 => {
+                self.either_to_guard(("Left", &ts.args[0]), ("Right", &ts.args[1]), obj, depth)
+            }
+            "Fn" | "FnOnce" | "FnMut" => obj.typeof_is("function"),
+            _ => {
+                let owned = ts.path();
+                let path: Vec<&str> = owned.iter().map(|s| s.as_ref()).collect();
+                match path[..] {
+                    ["chrono", "DateTime"] => obj.typeof_is("string"),
+                    _ => {
+                        self.ctxt.add_dependency(&ts.ident);
+                        let mut args = vec![obj.clone()];
+                        args.extend(ts.args.iter().map(|ty| {
+                            arg_guard(self.type_to_ts(ty), depth, |e| {
+                                self.type_to_guard(ty, e, depth + 1)
+                            })
+                        }));
+                        Expr::ident(guard_ident(&ts.ident.to_string())).call(args)
+                    }
+                }
+            }
+        }
+    }
+
+    /// object keys are always strings in javascript so
+    /// check numeric keys with `!isNaN(+key)`. `None` if no check is required.
+    fn key_to_guard(&self, ty: &syn::Type, key: &Expr, depth: usize) -> Option<Expr> {
+        match self.get_path(ty) {
+            Some(ref ts) if is_number(&ts.ident.to_string()) => {
+                Some(!Expr::ident("isNaN").call(vec![Expr::Plus(Box::new(key.clone()))]))
+            }
+            Some(ref ts) if is_string(&ts.ident.to_string()) => None,
+            // typed as `string`, see `key_to_ts`
+            Some(ref ts) if self.ctxt.is_type_param(ts.ident.to_string()) => None,
+            _ => Some(self.type_to_guard(ty, key, depth)),
+        }
+    }
+
+    /// `{ Ok: T } | { Err: E }`
+    fn either_to_guard(
+        &self,
+        left: (&str, &syn::Type),
+        right: (&str, &syn::Type),
+        obj: &Expr,
+        depth: usize,
+    ) -> Expr {
+        let variant = |(name, ty): (&str, &syn::Type)| {
+            Expr::and(vec![
+                obj.has(name),
+                self.type_to_guard(ty, &obj.member(name), depth),
+            ])
+        };
+        Expr::and(vec![
+            object_guard(obj),
+            Expr::or(vec![variant(left), variant(right)]),
+        ])
+    }
+
+    fn array_to_guard(&self, elem: &syn::Type, obj: &Expr, depth: usize) -> Expr {
+        // check for [u8] or Vec<u8>
+        if let Some(ty) = self.get_path(elem) {
+            if ty.ident == "u8" && matches!(self.field, Some(f) if is_bytes(f)) {
+                return obj.typeof_is("string");
+            };
+            if ty.ident == "u8" && self.is_byte_array() {
+                return obj.instance_of("Uint8Array");
+            };
+        };
+        array_guard(obj, depth, |e| self.type_to_guard(elem, e, depth + 1))
+    }
+
+    /// # convert a `syn::Type` rust type to a
+    /// javascript expression checking that `obj` is of that type.
+    ///
+    /// `depth` is used to generate unique names for nested lambda arguments.
+    pub fn type_to_guard(&self, ty: &syn::Type, obj: &Expr, depth: usize) -> Expr {
+        use syn::Type::*;
+        use syn::{
+            TypeGroup, TypeImplTrait, TypeParamBound, TypeParen, TypePath, TypePtr, TypeReference,
+            TypeSlice, TypeTraitObject, TypeTuple,
+        };
+        match ty {
+            Array(array) => match self.tuple_len(array) {
+                // `Array.isArray(obj) && obj.length === 4 && obj.every(...)`
+                Some(len) => tuple_guard(
+                    obj,
+                    len,
+                    std::iter::once(every_guard(obj, depth, |e| {
+                        self.type_to_guard(&array.elem, e, depth + 1)
+                    })),
+                ),
+                None => self.array_to_guard(&array.elem, obj, depth),
+            },
+            Slice(TypeSlice { elem, .. }) | Ptr(TypePtr { elem, .. }) => {
+                self.array_to_guard(elem, obj, depth)
+            }
+            Reference(TypeReference { elem, .. }) => self.type_to_guard(elem, obj, depth),
+            BareFn(..) => obj.typeof_is("function"),
+            Never(..) => Expr::Lit(Lit::Bool(false)),
+            Tuple(TypeTuple { elems, .. }) => tuple_guard(
+                obj,
+                elems.len(),
+                elems
+                    .iter()
+                    .enumerate()
+                    .map(|(i, t)| self.type_to_guard(t, &obj.index(Expr::num(i)), depth)),
+            ),
+            Path(TypePath { path, .. }) => match last_path_element(path) {
+                Some(ref ts) => self.generic_to_guard(ts, obj, depth),
+                _ => Expr::Lit(Lit::Bool(true)),
+            },
+            TraitObject(TypeTraitObject { bounds, .. })
+            | ImplTrait(TypeImplTrait { bounds, .. }) => Expr::and(
+                bounds
+                    .iter()
+                    .filter_map(|t| match t {
+                        TypeParamBound::Trait(t) => last_path_element(&t.path),
+                        _ => None, // skip lifetime etc.
+                    })
+                    .map(|t| self.generic_to_guard(&t, obj, depth))
+                    .collect(),
+            ),
+            Paren(TypeParen { elem, .. }) | Group(TypeGroup { elem, .. }) => {
+                self.type_to_guard(elem, obj, depth)
+            }
+            _ => Expr::Lit(Lit::Bool(true)),
+        }
+    }
+}
+
+impl<'a> ParseContext {
+    /// returns a guard expression checking `obj` is the type of `field`
+    pub(crate) fn field_to_guard(&self, field: &ast::Field<'a>, obj: &Expr) -> Expr {
+        let attrs = self.field_attrs(field);
+        // a user provided guard type has precedence over a user provided type
+        if let Some(ref s) = attrs.ts_guard {
+            return match Type::parse(s) {
+                // asked for, so every name is checked with its guard
+                Ok(ty) => type_guard(&|_| true, &ty, obj, 0),
+                Err(msg) => {
+                    self.err_msg(
+                        field.original,
+                        &format!("{}: can't parse ts_guard \"{}\": {}", self.ident, s, msg),
+                    );
+                    Expr::Lit(Lit::Bool(true))
+                }
+            };
+        }
+        if let Some(ref s) = attrs.ts_type {
+            // we can't be sure we understand arbitrary typescript so don't fail
+            return match Type::parse(s) {
+                Ok(ty) => type_guard(&|n| self.is_type_param(n), &ty, obj, 0),
+                Err(..) => Expr::Lit(Lit::Bool(true)),
+            };
+        }
+        let fc = FieldContext {
+            attrs,
+            ctxt: self,
+            field: Some(field),
+        };
+        if let Some(ref ty) = fc.attrs.ts_as {
+            fc.type_to_guard(ty, obj, 0)
+        } else {
+            fc.type_to_guard(field.ty, obj, 0)
+        }
+    }
+
+    /// returns a guard checking `obj` is the `T` of an `Option<T>` field
+    fn field_option_to_guard(&self, field: &ast::Field<'a>, obj: &Expr) -> Expr {
+        let attrs = self.field_attrs(field);
+        let ty = option_type(attrs.ts_as.as_ref().unwrap_or(field.ty)).expect("field is an Option");
+        let fc = FieldContext {
+            attrs,
+            ctxt: self,
+            field: Some(field),
+        };
+        fc.type_to_guard(&ty, obj, 0)
+    }
+
+    /// returns a guard for each field of `{ a: A, b: B }` accessed as `obj.a`, `obj.b`.
+    pub(crate) fn derive_fields_guard(&self, fields: &[&ast::Field<'a>], obj: &Expr) -> Vec<Expr> {
+        let known = fields
+            .iter()
+            .filter(|f| !f.attrs.flatten())
+            .map(|f| self.serde_name(f.attrs.name()))
+            .collect::<Vec<_>>();
+        fields
+            .iter()
+            .map(|f| {
+                if f.attrs.flatten() {
+                    return self.flatten_to_guard(f, &known, obj);
+                }
+                let value = obj.member(self.serde_name(f.attrs.name()));
+                let attrs = self.field_attrs(f);
+                match self.optional_field(f, &attrs) {
+                    // a missing key
+                    Some(unwrap) => {
+                        let guard = if unwrap {
+                            self.field_option_to_guard(f, &value)
+                        } else {
+                            self.field_to_guard(f, &value)
+                        };
+                        Expr::or(vec![value.eq3(Expr::ident("undefined")), guard])
+                    }
+                    None => self.field_to_guard(f, &value),
+                }
+            })
+            .collect()
+    }
+
+    /// a `#[serde(flatten)]` field shares `obj` with the `known` fields
+    fn flatten_to_guard(&self, field: &ast::Field<'a>, known: &[String], obj: &Expr) -> Expr {
+        let attrs = self.field_attrs(field);
+        if attrs.ts_type.is_some() || attrs.ts_guard.is_some() {
+            return self.field_to_guard(field, obj);
+        }
+        let ty = attrs.ts_as.clone().unwrap_or_else(|| field.ty.clone());
+        if option_type(&ty).is_some() {
+            // Partial<T>: any key may be missing
+            return Expr::Lit(Lit::Bool(true));
+        }
+        let map_value = match ty {
+            syn::Type::Path(syn::TypePath { ref path, .. }) => last_path_element(path)
+                .filter(|ts| {
+                    (ts.ident == "HashMap" || ts.ident == "BTreeMap") && ts.args.len() == 2
+                })
+                .map(|ts| ts.args[1].clone()),
+            _ => None,
+        };
+        match map_value {
+            // every key that doesn't belong to another field is part of the map
+            Some(value) => {
+                let fc = FieldContext {
+                    attrs,
+                    ctxt: self,
+                    field: Some(field),
+                };
+                let known = Expr::Array(known.iter().map(Expr::str).collect());
+                entries_guard(obj, 0, |k, v| {
+                    Expr::or(vec![
+                        known.method("includes", vec![k.clone()]),
+                        fc.type_to_guard(&value, v, 1),
+                    ])
+                })
+            }
+            None => self.field_to_guard(field, obj),
+        }
+    }
+
+    /// returns a guard for a tuple `[A, B]`
+    pub(crate) fn derive_field_tuple_guard(&self, fields: &[&ast::Field<'a>], obj: &Expr) -> Expr {
+        tuple_guard(
+            obj,
+            fields.len(),
+            fields
+                .iter()
+                .enumerate()
+                .map(|(i, f)| self.field_to_guard(f, &obj.index(Expr::num(i)))),
+        )
+    }
+}
+
+/// a guard checking `obj` is a typescript type from `#[ts(ts_guard = "...")]`, a
+/// `ts_type` or a `[types]` mapping. Only the names that are `guarded` (have an
+/// `isName` guard) are checked, the rest are opaque
+fn type_guard(guarded: &dyn Fn(&str) -> bool, ty: &Type, obj: &Expr, depth: usize) -> Expr {
+    match ty {
+        Type::Lit(lit) => obj.eq3(Expr::Lit(lit.clone())),
+        Type::Ref(name, args) => match (name.as_ref(), &args[..]) {
+            ("number", [])
+            | ("string", [])
+            | ("boolean", [])
+            | ("bigint", [])
+            | ("symbol", [])
+            | ("function", []) => obj.typeof_is(name),
+            ("object", []) => object_guard(obj),
+            ("null", []) => obj.eq3(Expr::ident("null")),
+            ("undefined", []) | ("void", []) => obj.eq3(Expr::ident("undefined")),
+            ("any", []) | ("unknown", []) => Expr::Lit(Lit::Bool(true)),
+            ("never", []) => Expr::Lit(Lit::Bool(false)),
+            ("Readonly", [ty]) => type_guard(guarded, ty, obj, depth),
+            ("Array", [elem]) | ("ReadonlyArray", [elem]) => {
+                array_guard(obj, depth, |e| type_guard(guarded, elem, e, depth + 1))
+            }
+            ("Record", [_, value]) => Expr::and(vec![
+                object_guard(obj),
+                values_guard(obj, depth, |v| type_guard(guarded, value, v, depth + 1)),
+            ]),
+            // `Partial<Record<K, V>>`
+            ("Partial", [ty @ Type::Ref(record, _)]) if record == "Record" => {
+                type_guard(guarded, ty, obj, depth)
+            }
+            ("Map", [key, value]) | ("ReadonlyMap", [key, value]) => {
+                es_map_guard(obj, depth, |k, v| {
+                    Expr::and(vec![
+                        type_guard(guarded, key, k, depth + 1),
+                        type_guard(guarded, value, v, depth + 1),
+                    ])
+                })
+            }
+            _ if !guarded(name) => Expr::Lit(Lit::Bool(true)),
+            _ => {
+                let mut v = vec![obj.clone()];
+                v.extend(args.iter().map(|ty| {
+                    arg_guard(ty.clone(), depth, |e| type_guard(guarded, ty, e, depth + 1))
+                }));
+                Expr::ident(guard_ident(name)).call(v)
+            }
+        },
+        Type::Array(elem) => array_guard(obj, depth, |e| type_guard(guarded, elem, e, depth + 1)),
+        Type::Readonly(ty) => type_guard(guarded, ty, obj, depth),
+        Type::Tuple(elems) => tuple_guard(
+            obj,
+            elems.len(),
+            elems
+                .iter()
+                .enumerate()
+                .map(|(i, t)| type_guard(guarded, t, &obj.index(Expr::num(i)), depth)),
+        ),
+        Type::Object(members) => {
+            let mut v = vec![object_guard(obj)];
+            v.extend(members.iter().map(|m| match m {
+                Member::Property {
+                    key, optional, ty, ..
+                } => {
+                    let value = obj.member(key.as_str());
+                    let g = type_guard(guarded, ty, &value, depth);
+                    if *optional {
+                        Expr::or(vec![value.eq3(Expr::ident("undefined")), g])
+                    } else {
+                        g
+                    }
+                }
+                Member::Index { value, .. } => {
+                    values_guard(obj, depth, |v| type_guard(guarded, value, v, depth + 1))
+                }
+                Member::Method { name, .. } => obj.member(name.as_str()).typeof_is("function"),
+            }));
+            Expr::and(v)
+        }
+        Type::Union(v) => Expr::or(
+            v.iter()
+                .map(|t| type_guard(guarded, t, obj, depth))
+                .collect(),
+        ),
+        Type::Intersection(v) => Expr::and(
+            v.iter()
+                .map(|t| type_guard(guarded, t, obj, depth))
+                .collect(),
+        ),
+        Type::Function(..) => obj.typeof_is("function"),
+        // we can't check these
+        Type::Predicate(..)
+        | Type::Typeof(..)
+        | Type::Keyof(..)
+        | Type::IndexedAccess(..)
+        | Type::Raw(..) => Expr::Lit(Lit::Bool(true)),
+    }
+}
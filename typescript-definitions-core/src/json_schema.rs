@@ -0,0 +1,281 @@
+// Copyright 2019 Ian Castleden
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! # JSON Schema
+//!
+//! [JSON Schema](https://json-schema.org) for the types we generate, e.g.
+//! `{"properties":{"x":{"type":"number"}},"required":["x"],"title":"Point","type":"object"}`.
+//!
+//! Other types are referenced as `{"$ref":"#/$defs/Foo"}` (or `#/components/schemas/Foo`
+//! for OpenAPI) so each schema is one entry of a document holding all of them.
+
+use super::ts::{Lit, Member, Type};
+use super::{ParseContext, JSON_VALUE};
+use serde_json::{json, Map, Value};
+
+/// where a schema will live, which decides how it references other schemas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    /// JSON Schema draft 2020-12: `#/$defs/Foo`
+    JsonSchema,
+    /// OpenAPI 3.1 `components/schemas`: `#/components/schemas/Foo`
+    OpenApi,
+}
+
+impl Dialect {
+    fn reference(self, name: &str) -> Value {
+        match self {
+            Dialect::JsonSchema => json!({ "$ref": format!("#/$defs/{}", name) }),
+            Dialect::OpenApi => json!({ "$ref": format!("#/components/schemas/{}", name) }),
+        }
+    }
+}
+
+fn of_type(name: &str) -> Value {
+    json!({ "type": name })
+}
+
+fn lit_value(lit: &Lit) -> Value {
+    match lit {
+        Lit::Str(s) => Value::String(s.clone()),
+        Lit::Num(n) => serde_json::from_str(n).unwrap_or_else(|_| Value::String(n.clone())),
+        Lit::Bool(b) => Value::Bool(*b),
+    }
+}
+
+/// the value of a union member that is a single value (for `enum`)
+fn const_value(ty: &Type) -> Option<Value> {
+    match ty {
+        Type::Lit(lit) => Some(lit_value(lit)),
+        Type::Ref(name, args) if name == "null" && args.is_empty() => Some(Value::Null),
+        _ => None,
+    }
+}
+
+fn is_undefined(ty: &Type) -> bool {
+    *ty == Type::named("undefined")
+}
+
+/// the properties of an object type, or of the first object of an intersection
+/// (an internally tagged variant is `{ tag: "A" } & Flattened`)
+fn properties(ty: &Type) -> &[Member] {
+    match ty {
+        Type::Object(members) => members,
+        Type::Intersection(types) => types.first().map(properties).unwrap_or(&[]),
+        _ => &[],
+    }
+}
+
+/// the `"kind"` of `{ kind: "A"; ... } | { kind: "B"; ... }`: a property every variant
+/// has with a different string literal
+fn discriminator(variants: &[&Type]) -> Option<String> {
+    let tag_value = |v: &Type, key: &str| {
+        properties(v).iter().find_map(|m| match m {
+            Member::Property {
+                key: k,
+                optional: false,
+                ty: Type::Lit(Lit::Str(value)),
+                ..
+            } if k == key => Some(value.clone()),
+            _ => None,
+        })
+    };
+    if variants.len() < 2 {
+        return None;
+    }
+    properties(variants[0])
+        .iter()
+        .filter_map(|m| match m {
+            Member::Property { key, .. } => Some(key),
+            _ => None,
+        })
+        .find(|key| {
+            let mut values = variants
+                .iter()
+                .map(|v| tag_value(v, key))
+                .collect::<Option<Vec<_>>>()
+                .unwrap_or_default();
+            let n = values.len();
+            values.sort();
+            values.dedup();
+            n == variants.len() && values.len() == n
+        })
+        .cloned()
+}
+
+/// an externally tagged enum, `{ A: string } | { B: number } | "C"`: no value can
+/// match more than one variant
+fn is_exclusive(variants: &[&Type]) -> bool {
+    let mut keys = vec![];
+    for v in variants {
+        match v {
+            Type::Lit(..) => {}
+            Type::Object(members) => match &members[..] {
+                [Member::Property {
+                    key,
+                    optional: false,
+                    ..
+                }] => keys.push(key),
+                _ => return false,
+            },
+            _ => return false,
+        }
+    }
+    let n = keys.len();
+    keys.sort();
+    keys.dedup();
+    keys.len() == n
+}
+
+impl ParseContext {
+    /// the JSON Schema of what serde writes for the type `ty`
+    pub(crate) fn type_to_schema(&self, ty: &Type, dialect: Dialect) -> Value {
+        let to_schema = |ty: &Type| self.type_to_schema(ty, dialect);
+        match ty {
+            Type::Lit(lit) => json!({ "const": lit_value(lit) }),
+            Type::Ref(name, args) => match (name.as_ref(), &args[..]) {
+                ("number", []) | ("string", []) | ("boolean", []) | ("null", []) => of_type(name),
+                ("bigint", []) => of_type("integer"),
+                ("object", []) => of_type("object"),
+                ("never", []) => json!({ "not": {} }),
+                ("Date", []) => json!({ "type": "string", "format": "date-time" }),
+                ("Uint8Array", []) => {
+                    json!({ "type": "array", "items": { "type": "integer", "minimum": 0, "maximum": 255 } })
+                }
+                ("Array", [elem]) | ("ReadonlyArray", [elem]) => {
+                    json!({ "type": "array", "items": to_schema(elem) })
+                }
+                ("Readonly", [ty]) => to_schema(ty),
+                ("Record", [key, value])
+                | ("Map", [key, value])
+                | ("ReadonlyMap", [key, value]) => self.map_to_schema(key, value, dialect),
+                // `Partial<Record<K, V>>`: a map doesn't need every key anyway
+                ("Partial", [ty @ Type::Ref(record, _)]) if record == "Record" => to_schema(ty),
+                // a flattened `Option<T>`: all of `T` or none of it
+                ("Partial", [ty]) => json!({ "anyOf": [to_schema(ty), of_type("object")] }),
+                // `any`, `unknown`, `JsonValue` and type parameters: anything goes
+                ("any", [])
+                | ("unknown", [])
+                | ("undefined", [])
+                | ("void", [])
+                | (JSON_VALUE, []) => json!({}),
+                (name, []) if self.is_type_param(name) => json!({}),
+                // JSON Schema has no generics so `Foo<T>` is any `Foo`
+                (name, _) => dialect.reference(name),
+            },
+            Type::Array(elem) => json!({ "type": "array", "items": to_schema(elem) }),
+            Type::Readonly(ty) => to_schema(ty),
+            Type::Tuple(elems) => json!({
+                "type": "array",
+                "prefixItems": elems.iter().map(to_schema).collect::<Vec<_>>(),
+                "items": false,
+                "minItems": elems.len(),
+            }),
+            Type::Object(members) => self.object_to_schema(members, dialect),
+            Type::Union(types) => self.union_to_schema(types, dialect),
+            Type::Intersection(types) => {
+                json!({ "allOf": types.iter().map(to_schema).collect::<Vec<_>>() })
+            }
+            // only in declarations we make ourselves
+            Type::Function(..)
+            | Type::Predicate(..)
+            | Type::Typeof(..)
+            | Type::Keyof(..)
+            | Type::IndexedAccess(..)
+            | Type::Raw(..) => json!({}),
+        }
+    }
+
+    /// `{ a: A; b?: B }`, with the members' doc comments as their `description`
+    fn object_to_schema(&self, members: &[Member], dialect: Dialect) -> Value {
+        let mut schema = Map::new();
+        schema.insert("type".into(), json!("object"));
+        let mut properties = Map::new();
+        let mut required = vec![];
+        for member in members {
+            match member {
+                Member::Property {
+                    docs,
+                    key,
+                    optional,
+                    ty,
+                    ..
+                } => {
+                    let mut value = self.type_to_schema(ty, dialect);
+                    if !docs.is_empty() {
+                        value["description"] = Value::String(docs.join("\n"));
+                    }
+                    properties.insert(key.clone(), value);
+                    if !optional {
+                        required.push(Value::String(key.clone()));
+                    }
+                }
+                Member::Index { key, value } => {
+                    if let Value::Object(map) = self.map_to_schema(key, value, dialect) {
+                        schema.extend(map);
+                    }
+                }
+                // methods and call signatures aren't data
+                Member::Method { .. } => {}
+            }
+        }
+        if !properties.is_empty() {
+            schema.insert("properties".into(), Value::Object(properties));
+        }
+        if !required.is_empty() {
+            schema.insert("required".into(), Value::Array(required));
+        }
+        Value::Object(schema)
+    }
+
+    /// the keys of a JSON object are strings so `number` keys are digits
+    fn map_to_schema(&self, key: &Type, value: &Type, dialect: Dialect) -> Value {
+        let mut schema = json!({
+            "type": "object",
+            "additionalProperties": self.type_to_schema(value, dialect),
+        });
+        if *key == Type::named("number") {
+            schema["propertyNames"] = json!({ "pattern": "^-?[0-9]+$" });
+        } else if *key != Type::named("string") {
+            schema["propertyNames"] = self.type_to_schema(key, dialect);
+        }
+        schema
+    }
+
+    /// an enum's variants are a `oneOf`, with the OpenAPI `discriminator` when they are
+    /// tagged. `"a" | "b"` is an `enum` and an `undefined` option is left out.
+    fn union_to_schema(&self, types: &[Type], dialect: Dialect) -> Value {
+        let types = types
+            .iter()
+            .filter(|t| !is_undefined(t))
+            .collect::<Vec<_>>();
+        if let [ty] = &types[..] {
+            return self.type_to_schema(ty, dialect);
+        }
+        if let Some(values) = types
+            .iter()
+            .map(|t| const_value(t))
+            .collect::<Option<Vec<_>>>()
+        {
+            return json!({ "enum": values });
+        }
+        let options = types
+            .iter()
+            .map(|t| self.type_to_schema(t, dialect))
+            .collect::<Vec<_>>();
+        match discriminator(&types) {
+            Some(tag) if dialect == Dialect::OpenApi => json!({
+                "oneOf": options,
+                "discriminator": { "propertyName": tag },
+            }),
+            Some(..) => json!({ "oneOf": options }),
+            None if is_exclusive(&types) => json!({ "oneOf": options }),
+            None => json!({ "anyOf": options }),
+        }
+    }
+}
@@ -0,0 +1,33 @@
+// Copyright 2019 Ian Castleden
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The parser behind the `typescript-definitions` derive macros and binary.
+//!
+//! This is an implementation detail: use the
+//! [typescript-definitions](https://crates.io/crates/typescript-definitions) crate.
+
+use serde_derive_internals::{ast, Ctxt};
+use typescript_definitions_ast as ts;
+
+mod attrs;
+mod config;
+mod derive_enum;
+mod derive_struct;
+mod guard;
+mod json_schema;
+mod tests;
+mod tots;
+mod typescriptify;
+mod utils;
+mod zod;
+
+pub use config::Config;
+pub use json_schema::Dialect;
+pub use typescriptify::*;
+use utils::*;
+pub use utils::{ident_from_str, json_value_definition_json, JSON_VALUE, JSON_VALUE_DEFINITION};
@@ -0,0 +1,2083 @@
+#[allow(unused_imports)]
+use super::Typescriptify;
+
+#[cfg(test)]
+mod macro_test {
+    use super::Typescriptify;
+    use crate::config::Config;
+    use crate::json_schema::Dialect;
+    use quote::quote;
+
+    macro_rules! assert_conversion {
+        ($tokens:expr,$expected:expr) => {{
+            let declarations = Typescriptify::new($tokens)
+                .parse()
+                .export_type_definition_source()
+                .declarations;
+
+            assert_eq!(declarations, $expected)
+        }};
+    }
+
+    macro_rules! assert_guard {
+        ($tokens:expr,$expected:expr) => {{
+            let guard = Typescriptify::new($tokens)
+                .parse()
+                .export_type_guard_source();
+
+            assert_eq!(guard, $expected)
+        }};
+    }
+
+    macro_rules! assert_zod {
+        ($tokens:expr,$expected:expr) => {{
+            let zod = Typescriptify::new($tokens).parse().export_type_zod_source();
+
+            assert_eq!(zod, $expected)
+        }};
+    }
+
+    macro_rules! assert_schema {
+        ($tokens:expr,$dialect:expr,$expected:expr) => {{
+            let schema = Typescriptify::new($tokens)
+                .parse()
+                .export_json_schema_source($dialect);
+
+            assert_eq!(schema, $expected)
+        }};
+    }
+
+    macro_rules! assert_input {
+        ($tokens:expr,$expected:expr) => {{
+            let input = Typescriptify::new($tokens)
+                .parse_input()
+                .export_type_declaration_source();
+
+            assert_eq!(input, $expected)
+        }};
+    }
+
+    macro_rules! assert_dependencies {
+        ($tokens:expr,$expected:expr) => {{
+            let deps = Typescriptify::new($tokens).parse().dependencies();
+            let expected: &[&str] = &$expected;
+            assert_eq!(deps, expected)
+        }};
+    }
+
+    // The crate only converts complex types, so we need to wrap the type in a struct to check its
+    // conversion. We could also write these tests at the conversion logic inside the crate but
+    // right now I'm just tring to get as much value for as little effort as possible.
+    macro_rules! assert_type_conversion {
+        ($rust:ty,$ts:literal) => {
+            assert_conversion!(
+                struct_with_type!($rust),
+                format!("export type Test = {{ t: {} }}", $ts)
+            )
+        };
+    }
+
+    macro_rules! struct_with_type {
+        ($type:ty) => {
+            quote!(
+                struct Test {
+                    t: $type,
+                }
+            )
+        };
+    }
+
+    macro_rules! assert_converts_to_number {
+        ($type:ty) => {
+            assert_type_conversion!($type, "number");
+        };
+    }
+
+    // Untested Conversions:
+    //  Path, PathBuf
+    //  Box, Cow, Rc, Arc, Cell, RefCell
+    //  Duration
+    //  SystemTime
+    //  VecDeque, LinkedList
+    //  BTreeMap, BTreeSet
+    //  Fn, FnOnce, FnMut
+    //  TraitsObject, ImplTrait
+    //  Literally every other type
+
+    #[test]
+    fn boolean_type_conversion() {
+        assert_type_conversion!(bool, "boolean");
+    }
+
+    #[test]
+    fn numeric_type_conversions() {
+        assert_converts_to_number!(i8);
+        assert_converts_to_number!(i16);
+        assert_converts_to_number!(i32);
+        assert_converts_to_number!(i64);
+        assert_converts_to_number!(i128);
+        assert_converts_to_number!(isize);
+
+        assert_converts_to_number!(u8);
+        assert_converts_to_number!(u16);
+        assert_converts_to_number!(u32);
+        assert_converts_to_number!(u64);
+        assert_converts_to_number!(u128);
+        assert_converts_to_number!(usize);
+
+        assert_converts_to_number!(f32);
+        assert_converts_to_number!(f64);
+    }
+
+    #[test]
+    fn string_type_conversions() {
+        assert_type_conversion!(char, "string");
+
+        assert_type_conversion!(String, "string");
+        assert_type_conversion!(str, "string");
+    }
+
+    #[test]
+    fn collection_conversion() {
+        assert_type_conversion!([String], "string[]");
+        assert_type_conversion!(Vec<String>, "string[]");
+        assert_type_conversion!(HashMap<String, String>, "Record<string, string>");
+        assert_type_conversion!(HashSet<String>, "string[]");
+    }
+
+    #[test]
+    fn monad_conversion() {
+        assert_type_conversion!(Option<String>, "string | null");
+        assert_type_conversion!(Result<String, String>, "{ Ok: string } | { Err: string }");
+        assert_type_conversion!(Either<String, String>, "{ Left: string } | { Right: string }");
+    }
+
+    #[test]
+    fn nested_conversion_precedence() {
+        assert_type_conversion!(Vec<Option<String>>, "(string | null)[]");
+        assert_type_conversion!(Option<Vec<String>>, "string[] | null");
+        assert_type_conversion!(
+            Vec<Result<i32, String>>,
+            "({ Ok: number } | { Err: string })[]"
+        );
+    }
+
+    #[test]
+    fn non_identifier_keys_are_quoted() {
+        let tokens = quote!(
+            struct Headers {
+                #[serde(rename = "content-type")]
+                content_type: String,
+                #[serde(rename = "default")]
+                fallback: bool,
+            }
+        );
+
+        assert_conversion!(
+            tokens.clone(),
+            "export type Headers = { \"content-type\": string; \"default\": boolean }"
+        );
+        assert_guard!(tokens, "export function isHeaders(obj: any): obj is Headers {\n    return typeof obj === \"object\" && obj !== null && typeof obj[\"content-type\"] === \"string\" && typeof obj[\"default\"] === \"boolean\";\n}");
+    }
+
+    #[test]
+    fn non_identifier_enum_members_are_quoted() {
+        let tokens = quote!(
+            #[serde(rename_all = "kebab-case")]
+            enum Direction {
+                NorthEast,
+                SouthWest,
+            }
+        );
+
+        assert_conversion!(tokens, "export enum Direction { \"north-east\" = \"north-east\", \"south-west\" = \"south-west\" }");
+    }
+
+    #[test]
+    fn non_identifier_tags_and_handlers() {
+        let tokens = quote!(
+            #[serde(tag = "msg-kind", content = "data", rename_all = "kebab-case")]
+            enum Message {
+                SetName(String),
+                Clear,
+            }
+        );
+
+        assert_conversion!(tokens.clone(), "export type Message =\n    | { \"msg-kind\": \"set-name\"; data: string }\n    | { \"msg-kind\": \"clear\" }");
+        assert_guard!(tokens.clone(), "export function isMessage(obj: any): obj is Message {\n    return (typeof obj === \"object\" && obj !== null && obj[\"msg-kind\"] === \"set-name\" && typeof obj.data === \"string\") || (typeof obj === \"object\" && obj !== null && obj[\"msg-kind\"] === \"clear\");\n}");
+        let handler = Typescriptify::new(tokens)
+            .parse()
+            .export_type_handler_source()
+            .unwrap();
+        assert_eq!(handler, "export interface HandleMessage {\n    onSetName(message: string): any;\n    onclear(): any;\n}\nexport namespace Message {\n    export type SetName = string\n}\nexport function applyMessage(handler: HandleMessage): (input: Message) => any {\n    //@ts-ignore\n    return (input) => handler[{ \"set-name\": \"onSetName\", clear: \"onclear\" }[input[\"msg-kind\"]]](input.data);\n}\n");
+    }
+
+    #[test]
+    fn json_schemas() {
+        let tokens = quote!(
+            /// a point
+            struct Point {
+                /// across
+                x: i32,
+                y: Option<f64>,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                z: Option<String>,
+                pair: (i32, String),
+                names: HashMap<u32, String>,
+                near: Vec<Point>,
+            }
+        );
+        assert_schema!(
+            tokens,
+            Dialect::JsonSchema,
+            r##"{"description":"a point","properties":{"names":{"additionalProperties":{"type":"string"},"propertyNames":{"pattern":"^-?[0-9]+$"},"type":"object"},"near":{"items":{"$ref":"#/$defs/Point"},"type":"array"},"pair":{"items":false,"minItems":2,"prefixItems":[{"type":"number"},{"type":"string"}],"type":"array"},"x":{"description":"across","type":"number"},"y":{"anyOf":[{"type":"number"},{"type":"null"}]},"z":{"type":"string"}},"required":["x","y","pair","names","near"],"title":"Point","type":"object"}"##
+        );
+
+        let tokens = quote!(
+            #[serde(tag = "kind")]
+            enum Shape {
+                Circle { center: Point, radius: f64 },
+                Empty,
+            }
+        );
+        assert_schema!(
+            tokens.clone(),
+            Dialect::JsonSchema,
+            r##"{"oneOf":[{"properties":{"center":{"$ref":"#/$defs/Point"},"kind":{"const":"Circle"},"radius":{"type":"number"}},"required":["kind","center","radius"],"type":"object"},{"properties":{"kind":{"const":"Empty"}},"required":["kind"],"type":"object"}],"title":"Shape"}"##
+        );
+        assert_schema!(
+            tokens,
+            Dialect::OpenApi,
+            r##"{"discriminator":{"propertyName":"kind"},"oneOf":[{"properties":{"center":{"$ref":"#/components/schemas/Point"},"kind":{"const":"Circle"},"radius":{"type":"number"}},"required":["kind","center","radius"],"type":"object"},{"properties":{"kind":{"const":"Empty"}},"required":["kind"],"type":"object"}],"title":"Shape"}"##
+        );
+
+        let tokens = quote!(
+            #[serde(tag = "t", content = "c")]
+            enum Msg {
+                A(String),
+                B,
+            }
+        );
+        assert_schema!(
+            tokens,
+            Dialect::OpenApi,
+            r##"{"discriminator":{"propertyName":"t"},"oneOf":[{"properties":{"c":{"type":"string"},"t":{"const":"A"}},"required":["t","c"],"type":"object"},{"properties":{"t":{"const":"B"}},"required":["t"],"type":"object"}],"title":"Msg"}"##
+        );
+
+        let tokens = quote!(
+            enum Ext {
+                A(String),
+                B(i32, i32),
+                C,
+            }
+        );
+        assert_schema!(
+            tokens,
+            Dialect::JsonSchema,
+            r##"{"oneOf":[{"properties":{"A":{"type":"string"}},"required":["A"],"type":"object"},{"properties":{"B":{"items":false,"minItems":2,"prefixItems":[{"type":"number"},{"type":"number"}],"type":"array"}},"required":["B"],"type":"object"},{"const":"C"}],"title":"Ext"}"##
+        );
+
+        let tokens = quote!(
+            #[serde(untagged)]
+            enum U {
+                A(String),
+                B(i32),
+            }
+        );
+        assert_schema!(
+            tokens,
+            Dialect::JsonSchema,
+            r##"{"anyOf":[{"type":"string"},{"type":"number"}],"title":"U"}"##
+        );
+
+        let tokens = quote!(
+            enum Color {
+                Red,
+                Green,
+            }
+        );
+        assert_schema!(
+            tokens,
+            Dialect::JsonSchema,
+            r##"{"enum":["Red","Green"],"title":"Color"}"##
+        );
+
+        let tokens = quote!(
+            struct Value<T> {
+                value: T,
+                other: Option<Box<Value<Vec<T>>>>,
+            }
+        );
+        assert_schema!(
+            tokens,
+            Dialect::JsonSchema,
+            r##"{"properties":{"other":{"anyOf":[{"$ref":"#/$defs/Value"},{"type":"null"}]},"value":{}},"required":["value","other"],"title":"Value","type":"object"}"##
+        );
+    }
+
+    #[test]
+    fn zod_schemas() {
+        let tokens = quote!(
+            struct Point {
+                x: i32,
+                y: Option<f64>,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                z: Option<String>,
+                near: Vec<Point>,
+                names: HashMap<u32, String>,
+                pair: (i32, String),
+            }
+        );
+        assert_zod!(tokens, "export const PointSchema: z.ZodType<Point> = z.object({ x: z.number(), y: z.number().nullable(), z: z.string().optional(), near: z.array(z.lazy(() => PointSchema)), names: z.record(z.coerce.number(), z.string()), pair: z.tuple([z.number(), z.string()]) })\n");
+
+        let tokens = quote!(
+            #[serde(tag = "kind")]
+            enum Shape {
+                Circle { center: Point, radius: f64 },
+                Empty,
+            }
+        );
+        assert_zod!(tokens, "export const ShapeSchema: z.ZodType<Shape> = z.discriminatedUnion(\"kind\", [z.object({ kind: z.literal(\"Circle\"), center: z.lazy(() => PointSchema), radius: z.number() }), z.object({ kind: z.literal(\"Empty\") })])\n");
+
+        let tokens = quote!(
+            #[serde(tag = "kind", content = "data")]
+            enum Msg {
+                A(String),
+                B { x: i32 },
+            }
+        );
+        assert_zod!(tokens, "export const MsgSchema: z.ZodType<Msg> = z.discriminatedUnion(\"kind\", [z.object({ kind: z.literal(\"A\"), data: z.string() }), z.object({ kind: z.literal(\"B\"), data: z.object({ x: z.number() }) })])\n");
+
+        let tokens = quote!(
+            enum Ext {
+                A(String),
+                C,
+            }
+        );
+        assert_zod!(tokens, "export const ExtSchema: z.ZodType<Ext> = z.union([z.object({ A: z.string() }), z.literal(\"C\")])\n");
+
+        let tokens = quote!(
+            #[serde(untagged)]
+            enum U {
+                A(String),
+                B(i32),
+            }
+        );
+        assert_zod!(
+            tokens,
+            "export const USchema: z.ZodType<U> = z.union([z.string(), z.number()])\n"
+        );
+
+        let tokens = quote!(
+            enum Color {
+                Red,
+                Green,
+            }
+        );
+        assert_zod!(
+            tokens,
+            "export const ColorSchema: z.ZodType<Color> = z.nativeEnum(Color)\n"
+        );
+        let tokens = quote!(
+            #[ts(enum_style = "const_enum")]
+            enum Color {
+                Red,
+                Green,
+            }
+        );
+        assert_zod!(tokens, "export const ColorSchema: z.ZodType<Color> = z.union([z.literal(Color.Red), z.literal(Color.Green)])\n");
+        let tokens = quote!(
+            #[ts(enum_style = "union")]
+            enum Color {
+                Red,
+                Green,
+            }
+        );
+        assert_zod!(tokens, "export const ColorSchema: z.ZodType<Color> = z.union([z.literal(\"Red\"), z.literal(\"Green\")])\n");
+
+        let tokens = quote!(
+            struct Value<T> {
+                value: T,
+                more: Option<Box<Value<Vec<T>>>>,
+            }
+        );
+        assert_zod!(tokens, "export const ValueSchema = <T extends z.ZodTypeAny>(TSchema: T) => z.object({ value: TSchema, more: z.lazy(() => ValueSchema(z.array(TSchema))).nullable() })\n");
+    }
+
+    #[test]
+    fn opaque_names_zod() {
+        let tokens = quote!(
+            struct User {
+                id: Uuid,
+                #[ts(ts_type = "Date | Moment")]
+                at: Time,
+                friend: Friend,
+            }
+        );
+        let tsy = with_types(tokens, "[types]\nUuid = \"UUID\"");
+        // there is no `UUIDSchema` or `MomentSchema`
+        assert_eq!(tsy.parse().export_type_zod_source(), "export const UserSchema: z.ZodType<User> = z.object({ id: z.custom<UUID>(), at: z.union([z.date(), z.custom<Moment>()]), friend: z.lazy(() => FriendSchema) })\n");
+    }
+
+    #[test]
+    fn reference_conversion() {
+        assert_type_conversion!(&str, "string");
+    }
+
+    #[test]
+    fn tuple_conversion() {
+        assert_type_conversion!((i32, String), "[number, string]")
+    }
+
+    #[test]
+    fn fixed_array_conversion() {
+        assert_type_conversion!([u8; 4], "[number, number, number, number]");
+        assert_type_conversion!([String; 0], "[]");
+        assert_type_conversion!([f64; 17], "number[]");
+        assert_type_conversion!([f64; N], "number[]");
+
+        let tokens = quote!(
+            #[ts(tuple_limit = 2)]
+            struct S<const N: usize> {
+                a: [i32; 2],
+                b: [i32; 3],
+                #[ts(tuple_limit = 3)]
+                c: [i32; 3],
+                d: [i32; N],
+            }
+        );
+        let parsed = Typescriptify::new(tokens.clone()).parse();
+        assert_eq!(parsed.export_type_definition_source().declarations, "export type S = { a: [number, number]; b: number[]; c: [number, number, number]; d: number[] }");
+        assert_guard!(tokens, "export function isS(obj: any): obj is S {\n    return typeof obj === \"object\" && obj !== null && Array.isArray(obj.a) && obj.a.length === 2 && obj.a.every((e0: any) => typeof e0 === \"number\") && Array.isArray(obj.b) && obj.b.every((e0: any) => typeof e0 === \"number\") && Array.isArray(obj.c) && obj.c.length === 3 && obj.c.every((e0: any) => typeof e0 === \"number\") && Array.isArray(obj.d) && obj.d.every((e0: any) => typeof e0 === \"number\");\n}");
+
+        // `serde_bytes` writes bytes of any length
+        let fields = quote!(
+            #[serde(with = "serde_bytes")]
+            a: [u8; 4],
+        );
+        assert_conversion!(
+            quote!(struct S { #fields }),
+            "export type S = { a: number[] }"
+        );
+        assert_conversion!(
+            quote!(#[ts(profile = "msgpack")] struct S { #fields }),
+            "export type S = { a: Uint8Array }"
+        );
+    }
+
+    #[test]
+    fn custom_type_conversion() {
+        assert_type_conversion!(MyCustomType, "MyCustomType")
+    }
+
+    // This is tested implicitly in other tests but it's ok to be explicit, plus this
+    // one tests structs with multiple fields
+    #[test]
+    fn struct_conversion() {
+        let tokens = quote!(
+            struct Test {
+                i: i32,
+                s: String,
+            }
+        );
+        assert_conversion!(tokens, "export type Test = { i: number; s: string }")
+    }
+
+    #[test]
+    fn simple_enum_is_converted() {
+        let tokens = quote!(
+            #[serde(tag = "t", content = "c")]
+            enum SimpleEnum {
+                Foo,
+                Bar,
+            }
+        );
+        assert_conversion!(
+            tokens,
+            "export type SimpleEnum =\n    | { t: \"Foo\" }\n    | { t: \"Bar\" }"
+        )
+    }
+
+    #[test]
+    fn enum_with_struct_variants_is_converted() {
+        let tokens = quote!(
+            #[serde(tag = "t", content = "c")]
+            enum ComplexEnum {
+                Foo(String),
+                Bar { Baz: i32 },
+            }
+        );
+        assert_conversion!(
+            tokens, "export type ComplexEnum =\n    | { t: \"Foo\"; c: string }\n    | { t: \"Bar\"; c: { Baz: number } }"
+        )
+    }
+
+    #[test]
+    fn enum_with_complex_inner_types_is_converted() {
+        let tokens = quote!(
+            #[serde(tag = "t", content = "c")]
+            enum ComplexEnum {
+                Foo(Vec<Bar>),
+                Bar(Option<Baz>),
+            }
+        );
+        assert_conversion!(
+            tokens, "export type ComplexEnum =\n    | { t: \"Foo\"; c: Bar[] }\n    | { t: \"Bar\"; c: Baz | null }"
+        )
+    }
+
+    #[test]
+    fn doc_comments_in_fields() {
+        let tokens = quote!(
+            /// a well-documented struct
+            struct WellDocumented {
+                /// even the field is documented
+                well_documented: String,
+            }
+        );
+        assert_conversion!(tokens, "/**\n * a well-documented struct\n */\nexport type WellDocumented = {\n    /**\n     * even the field is documented\n     */\n    well_documented: string;\n}");
+    }
+
+    #[test]
+    fn doc_c_like_enum_variant() {
+        let tokens = quote!(
+            enum CLikeEnum {
+                /// a well-documented variant
+                One,
+                /// another well-documented variant
+                /// this time with multiple lines
+                Other,
+            }
+        );
+        assert_conversion!(tokens, "export enum CLikeEnum {\n    /**\n     * a well-documented variant\n     */\n    One = \"One\",\n    /**\n     * another well-documented variant\n     * this time with multiple lines\n     */\n    Other = \"Other\",\n}");
+    }
+
+    #[test]
+    fn doc_unit_enum_variant() {
+        let tokens = quote!(
+            #[serde(tag = "t", content = "c")]
+            enum UnitEnum {
+                /// a well-documented variant
+                One(),
+                /// another well-documented variant
+                /// this time with multiple lines
+                Other(),
+            }
+        );
+        assert_conversion!(tokens, "export type UnitEnum =\n    | {\n        /**\n         * a well-documented variant\n         */\n        t: \"One\";\n        c: [];\n    }\n    | {\n        /**\n         * another well-documented variant\n         * this time with multiple lines\n         */\n        t: \"Other\";\n        c: [];\n    }");
+    }
+
+    #[test]
+    fn doc_newtype_enum_variant() {
+        let tokens = quote!(
+            #[serde(tag = "t", content = "c")]
+            enum NewtypeEnum {
+                /// a well-documented variant
+                One(u32),
+                /// another well-documented variant
+                /// this time with multiple lines
+                Other(String),
+            }
+        );
+        assert_conversion!(tokens, "export type NewtypeEnum =\n    | {\n        /**\n         * a well-documented variant\n         */\n        t: \"One\";\n        c: number;\n    }\n    | {\n        /**\n         * another well-documented variant\n         * this time with multiple lines\n         */\n        t: \"Other\";\n        c: string;\n    }");
+    }
+
+    #[test]
+    fn doc_tuple_enum_variant() {
+        let tokens = quote!(
+            #[serde(tag = "t", content = "c")]
+            enum TupleEnum {
+                /// a well-documented variant
+                One(u32, String),
+                /// another well-documented variant
+                /// this time with multiple lines
+                Other(usize, usize),
+            }
+        );
+        assert_conversion!(tokens, "export type TupleEnum =\n    | {\n        /**\n         * a well-documented variant\n         */\n        t: \"One\";\n        c: [number, string];\n    }\n    | {\n        /**\n         * another well-documented variant\n         * this time with multiple lines\n         */\n        t: \"Other\";\n        c: [number, number];\n    }" );
+    }
+
+    #[test]
+    fn doc_struct_enum_variant() {
+        let tokens = quote!(
+            #[serde(tag = "t", content = "c")]
+            enum StructEnum {
+                /// a well-documented variant
+                One {
+                    /// fields in variants
+                    x: f32,
+                    /// can be documented, too.
+                    y: f32,
+                },
+                /// another well-documented variant
+                /// this time with multiple lines
+                Other { name: String },
+            }
+        );
+        assert_conversion!(tokens, "export type StructEnum =\n    | {\n        /**\n         * a well-documented variant\n         */\n        t: \"One\";\n        c: {\n            /**\n             * fields in variants\n             */\n            x: number;\n            /**\n             * can be documented, too.\n             */\n            y: number;\n        };\n    }\n    | {\n        /**\n         * another well-documented variant\n         * this time with multiple lines\n         */\n        t: \"Other\";\n        c: { name: string };\n    }");
+    }
+
+    #[test]
+    fn struct_guard() {
+        let tokens = quote!(
+            struct Test {
+                i: i32,
+                s: Option<String>,
+                v: Vec<u8>,
+                m: HashMap<u32, bool>,
+                t: (i32, String),
+            }
+        );
+        assert_guard!(tokens, "export function isTest(obj: any): obj is Test {\n    return typeof obj === \"object\" && obj !== null && typeof obj.i === \"number\" && (obj.s === null || typeof obj.s === \"string\") && Array.isArray(obj.v) && obj.v.every((e0: any) => typeof e0 === \"number\") && typeof obj.m === \"object\" && obj.m !== null && Object.entries(obj.m).every(([k0, v0]) => !isNaN(+k0) && typeof v0 === \"boolean\") && Array.isArray(obj.t) && obj.t.length === 2 && typeof obj.t[0] === \"number\" && typeof obj.t[1] === \"string\";\n}");
+    }
+
+    #[test]
+    fn generic_guard() {
+        let tokens = quote!(
+            struct Test<T> {
+                value: T,
+                other: Value<Vec<i32>>,
+            }
+        );
+        assert_guard!(tokens, "export function isTest<T>(obj: any, isT: (obj: any) => obj is T): obj is Test<T> {\n    return typeof obj === \"object\" && obj !== null && isT(obj.value) && isValue(obj.other, (e0: any): e0 is number[] => Array.isArray(e0) && e0.every((e1: any) => typeof e1 === \"number\"));\n}");
+    }
+
+    #[test]
+    fn ts_guard_attribute() {
+        let tokens = quote!(
+            struct Test {
+                #[ts(ts_guard = "{ value: number[] }")]
+                value: Value<Vec<i32>>,
+                #[ts(ts_type = "string | null")]
+                other: Other,
+            }
+        );
+        assert_guard!(tokens, "export function isTest(obj: any): obj is Test {\n    return typeof obj === \"object\" && obj !== null && typeof obj.value === \"object\" && obj.value !== null && Array.isArray(obj.value.value) && obj.value.value.every((e0: any) => typeof e0 === \"number\") && (typeof obj.other === \"string\" || obj.other === null);\n}");
+    }
+
+    #[test]
+    fn c_like_enum_guard() {
+        let tokens = quote!(
+            enum CLike {
+                A,
+                B,
+            }
+        );
+        assert_guard!(tokens, "export function isCLike(obj: any): obj is CLike {\n    return obj === \"A\" || obj === \"B\";\n}");
+    }
+
+    #[test]
+    fn external_enum_guard() {
+        let tokens = quote!(
+            enum External {
+                A,
+                B(i32),
+                C { x: String },
+                D(i32, i32),
+            }
+        );
+        assert_guard!(tokens, "export function isExternal(obj: any): obj is External {\n    return obj === \"A\" || (typeof obj === \"object\" && obj !== null && \"B\" in obj && typeof obj.B === \"number\") || (typeof obj === \"object\" && obj !== null && \"C\" in obj && typeof obj.C === \"object\" && obj.C !== null && typeof obj.C.x === \"string\") || (typeof obj === \"object\" && obj !== null && \"D\" in obj && Array.isArray(obj.D) && obj.D.length === 2 && typeof obj.D[0] === \"number\" && typeof obj.D[1] === \"number\");\n}");
+    }
+
+    #[test]
+    fn internal_enum_guard() {
+        let tokens = quote!(
+            #[serde(tag = "t")]
+            enum Internal {
+                A,
+                B(Inner),
+                C { x: String },
+            }
+        );
+        assert_conversion!(tokens.clone(), "export type Internal =\n    | { t: \"A\" }\n    | { t: \"B\" } & Inner\n    | { t: \"C\"; x: string }");
+        assert_zod!(tokens.clone(), "export const InternalSchema: z.ZodType<Internal> = z.union([z.object({ t: z.literal(\"A\") }), z.object({ t: z.literal(\"B\") }).and(z.lazy(() => InnerSchema)), z.object({ t: z.literal(\"C\"), x: z.string() })])\n");
+        assert_schema!(tokens.clone(), Dialect::JsonSchema, "{\"oneOf\":[{\"properties\":{\"t\":{\"const\":\"A\"}},\"required\":[\"t\"],\"type\":\"object\"},{\"allOf\":[{\"properties\":{\"t\":{\"const\":\"B\"}},\"required\":[\"t\"],\"type\":\"object\"},{\"$ref\":\"#/$defs/Inner\"}]},{\"properties\":{\"t\":{\"const\":\"C\"},\"x\":{\"type\":\"string\"}},\"required\":[\"t\",\"x\"],\"type\":\"object\"}],\"title\":\"Internal\"}");
+        assert_guard!(tokens, "export function isInternal(obj: any): obj is Internal {\n    return (typeof obj === \"object\" && obj !== null && obj.t === \"A\") || (typeof obj === \"object\" && obj !== null && obj.t === \"B\" && isInner(obj)) || (typeof obj === \"object\" && obj !== null && obj.t === \"C\" && typeof obj.x === \"string\");\n}");
+    }
+
+    #[test]
+    fn adjacent_enum_guard() {
+        let tokens = quote!(
+            #[serde(tag = "t", content = "c")]
+            enum Adjacent {
+                A,
+                B(i32),
+                C { x: String },
+                D(i32, i32),
+            }
+        );
+        assert_guard!(tokens, "export function isAdjacent(obj: any): obj is Adjacent {\n    return (typeof obj === \"object\" && obj !== null && obj.t === \"A\") || (typeof obj === \"object\" && obj !== null && obj.t === \"B\" && typeof obj.c === \"number\") || (typeof obj === \"object\" && obj !== null && obj.t === \"C\" && typeof obj.c === \"object\" && obj.c !== null && typeof obj.c.x === \"string\") || (typeof obj === \"object\" && obj !== null && obj.t === \"D\" && Array.isArray(obj.c) && obj.c.length === 2 && typeof obj.c[0] === \"number\" && typeof obj.c[1] === \"number\");\n}");
+    }
+
+    #[test]
+    fn untagged_enum_guard() {
+        let tokens = quote!(
+            #[serde(untagged)]
+            enum Untagged {
+                B(i32),
+                C { x: String },
+                D(i32, i32),
+            }
+        );
+        assert_guard!(tokens, "export function isUntagged(obj: any): obj is Untagged {\n    return typeof obj === \"number\" || (typeof obj === \"object\" && obj !== null && typeof obj.x === \"string\") || (Array.isArray(obj) && obj.length === 2 && typeof obj[0] === \"number\" && typeof obj[1] === \"number\");\n}");
+    }
+
+    #[test]
+    fn flatten_struct() {
+        let tokens = quote!(
+            struct Page {
+                a: i32,
+                #[serde(flatten)]
+                pagination: Pagination,
+                #[serde(flatten)]
+                meta: Option<Meta>,
+            }
+        );
+        assert_conversion!(
+            tokens,
+            "export type Page = { a: number } & Pagination & Partial<Meta>"
+        );
+    }
+
+    #[test]
+    fn flatten_map() {
+        let tokens = quote!(
+            struct Extra {
+                a: i32,
+                #[serde(flatten)]
+                extra: HashMap<String, String>,
+            }
+        );
+        assert_conversion!(
+            tokens,
+            "export type Extra = { a: number } & Record<string, string>"
+        );
+    }
+
+    #[test]
+    fn flatten_only() {
+        let tokens = quote!(
+            struct Only {
+                #[serde(flatten)]
+                pagination: Pagination,
+            }
+        );
+        assert_conversion!(tokens, "export type Only = Pagination");
+    }
+
+    #[test]
+    fn flatten_struct_variant() {
+        let tokens = quote!(
+            #[serde(tag = "t")]
+            enum Internal {
+                A {
+                    a: i32,
+                    #[serde(flatten)]
+                    pagination: Pagination,
+                },
+            }
+        );
+        assert_conversion!(
+            tokens,
+            "export type Internal = { t: \"A\"; a: number } & Pagination"
+        );
+        let tokens = quote!(
+            #[serde(tag = "t", content = "c")]
+            enum Adjacent {
+                A {
+                    a: i32,
+                    #[serde(flatten)]
+                    pagination: Pagination,
+                },
+            }
+        );
+        assert_conversion!(
+            tokens,
+            "export type Adjacent = { t: \"A\"; c: { a: number } & Pagination }"
+        );
+    }
+
+    #[test]
+    fn flatten_guard() {
+        let tokens = quote!(
+            struct Extra {
+                a: i32,
+                #[serde(flatten)]
+                pagination: Pagination,
+                #[serde(flatten)]
+                extra: HashMap<String, u32>,
+            }
+        );
+        assert_guard!(tokens, "export function isExtra(obj: any): obj is Extra {\n    return typeof obj === \"object\" && obj !== null && typeof obj.a === \"number\" && isPagination(obj) && Object.entries(obj).every(([k0, v0]) => [\"a\"].includes(k0) || typeof v0 === \"number\");\n}");
+    }
+
+    #[test]
+    fn dependencies() {
+        let tokens = quote!(
+            struct Message<T> {
+                borrow: Value<i32>,
+                map: HashMap<String, Vec<Point>>,
+                again: Option<Point>,
+                generic: T,
+                next: Option<Box<Message<T>>>,
+                time: chrono::DateTime<Utc>,
+                #[ts(ts_type = "Opaque")]
+                opaque: Hidden,
+                #[serde(flatten)]
+                flat: Pagination,
+            }
+        );
+        assert_dependencies!(tokens, ["Value", "Point", "Pagination"]);
+        let tokens = quote!(
+            #[serde(tag = "t")]
+            enum Tree {
+                Leaf(Leaf),
+                Node { left: Box<Tree>, right: Box<Tree> },
+            }
+        );
+        assert_dependencies!(tokens, ["Leaf"]);
+    }
+
+    #[test]
+    fn input_struct() {
+        let tokens = quote!(
+            struct Query<T> {
+                #[serde(rename(serialize = "query", deserialize = "q"))]
+                query: String,
+                #[serde(default)]
+                page: u32,
+                #[serde(skip_deserializing)]
+                total: u32,
+                #[serde(skip_serializing)]
+                token: String,
+                filter: Option<Filter>,
+                #[serde(deserialize_with = "custom")]
+                strict: Option<i32>,
+                extra: T,
+            }
+        );
+        assert_input!(
+            tokens.clone(),
+            "export type QueryInput<T> = { q: string; page?: number; token: string; filter?: FilterInput | null; strict: number | null; extra: T }"
+        );
+        assert_conversion!(tokens, "export type Query<T> = { query: string; page: number; total: number; filter: Filter | null; strict: number | null; extra: T }");
+    }
+
+    #[test]
+    fn input_wanted() {
+        let wants_input = |tokens| Typescriptify::new(tokens).wants_input();
+        assert!(!wants_input(quote!(
+            struct S;
+        )));
+        assert!(wants_input(quote!(
+            #[ts(input)]
+            struct S;
+        )));
+        assert!(!wants_input(quote!(
+            #[ts(input = false)]
+            struct S;
+        )));
+        assert!(with_types(
+            quote!(
+                struct S;
+            ),
+            "[defaults]\ninput = true"
+        )
+        .wants_input());
+    }
+
+    #[test]
+    fn input_container_default() {
+        let tokens = quote!(
+            #[serde(default)]
+            struct Config {
+                a: i32,
+                b: Vec<Item>,
+            }
+        );
+        assert_input!(
+            tokens,
+            "export type ConfigInput = { a?: number; b?: ItemInput[] }"
+        );
+    }
+
+    #[test]
+    fn input_enum() {
+        let tokens = quote!(
+            #[serde(tag = "t")]
+            enum Command {
+                #[serde(rename(deserialize = "start"))]
+                Start {
+                    at: Time,
+                },
+                #[serde(skip_deserializing)]
+                Internal,
+                Stop,
+            }
+        );
+        assert_input!(
+            tokens,
+            "export type CommandInput =\n    | { t: \"start\"; at: TimeInput }\n    | { t: \"Stop\" }"
+        );
+        let tokens = quote!(
+            enum Color {
+                #[serde(rename(deserialize = "r"))]
+                Red,
+                Green,
+            }
+        );
+        assert_input!(
+            tokens,
+            "export enum ColorInput { r = \"r\", Green = \"Green\" }"
+        );
+    }
+
+    #[test]
+    fn optional_fields() {
+        let tokens = quote!(
+            struct S {
+                #[serde(skip_serializing_if = "Option::is_none")]
+                a: Option<i32>,
+                #[serde(skip_serializing_if = "Vec::is_empty")]
+                b: Vec<String>,
+                #[ts(optional)]
+                c: Option<bool>,
+                #[ts(optional)]
+                d: u8,
+                e: Option<i32>,
+            }
+        );
+        assert_conversion!(tokens, "export type S = { a?: number; b?: string[]; c?: boolean; d?: number; e: number | null }");
+    }
+
+    #[test]
+    fn optional_fields_guard() {
+        let tokens = quote!(
+            struct S {
+                #[serde(skip_serializing_if = "Option::is_none")]
+                a: Option<i32>,
+                #[ts(optional)]
+                d: u8,
+            }
+        );
+        assert_guard!(tokens, "export function isS(obj: any): obj is S {\n    return typeof obj === \"object\" && obj !== null && (obj.a === undefined || typeof obj.a === \"number\") && (obj.d === undefined || typeof obj.d === \"number\");\n}");
+    }
+
+    #[test]
+    fn field_attribute_errors_once() {
+        let tokens = quote!(
+            struct S {
+                #[ts(optinal)]
+                a: Option<i32>,
+            }
+        );
+        let mut parsed = Typescriptify::new(tokens).parse();
+        assert_eq!(parsed.check().map_err(|errs| errs.len()), Err(1));
+    }
+
+    #[test]
+    fn option_style_undefined() {
+        let tokens = quote!(
+            #[ts(option_style = "undefined")]
+            struct S {
+                a: Option<i32>,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                b: Option<i32>,
+            }
+        );
+        assert_conversion!(
+            tokens,
+            "export type S = { a: number | undefined; b?: number }"
+        );
+    }
+
+    #[test]
+    fn option_style_optional() {
+        let tokens = quote!(
+            #[ts(option_style = "optional")]
+            struct S {
+                a: Option<i32>,
+                b: Option<Vec<i32>>,
+            }
+        );
+        assert_conversion!(tokens, "export type S = { a?: number; b?: number[] }");
+    }
+
+    #[test]
+    fn option_style_optional_guard() {
+        let tokens = quote!(
+            #[ts(option_style = "optional")]
+            struct S {
+                a: Option<i32>,
+            }
+        );
+        assert_guard!(tokens, "export function isS(obj: any): obj is S {\n    return typeof obj === \"object\" && obj !== null && (obj.a === undefined || typeof obj.a === \"number\");\n}");
+    }
+
+    #[test]
+    fn generic_struct() {
+        let tokens = quote!(
+            struct Value<'a, T, U = i32, V = Item> {
+                value: &'a T,
+                others: Vec<U>,
+                #[ts(ts_type = "V[]")]
+                more: Vec<Item>,
+            }
+        );
+        assert_conversion!(
+            tokens,
+            "export type Value<T, U = number, V = Item> = { value: T; others: U[]; more: V[] }"
+        );
+    }
+
+    #[test]
+    fn generic_values() {
+        let tokens = quote!(
+            struct Value<T> {
+                value: T,
+            }
+        );
+        let value = Typescriptify::new(tokens).parse().type_script_ify_source();
+        assert_eq!(value, "export type Value<T> = { value: T }\nexport const Value = <T>(check: Value<T>) => check\n");
+    }
+
+    #[test]
+    fn generic_input() {
+        let tokens = quote!(
+            struct Value<T, U = Item> {
+                value: T,
+                other: U,
+            }
+        );
+        assert_input!(
+            tokens,
+            "export type ValueInput<T, U = ItemInput> = { value: T; other: U }"
+        );
+    }
+
+    #[test]
+    fn generic_bound() {
+        let tokens = quote!(
+            #[ts(bound = "T: { id: number }")]
+            struct Value<T: Identified = Thing> {
+                value: T,
+            }
+        );
+        assert_conversion!(
+            tokens,
+            "export type Value<T extends { id: number } = Thing> = { value: T }"
+        );
+    }
+
+    #[test]
+    fn generic_bound_guard() {
+        let tokens = quote!(
+            #[ts(bound = "T: { id: number }")]
+            struct Value<T: Identified> {
+                value: T,
+            }
+        );
+        assert_guard!(tokens, "export function isValue<T extends { id: number }>(obj: any, isT: (obj: any) => obj is T): obj is Value<T> {\n    return typeof obj === \"object\" && obj !== null && isT(obj.value);\n}");
+    }
+
+    #[test]
+    fn generic_phantom() {
+        let tokens = quote!(
+            struct Id<T, U> {
+                id: u64,
+                value: U,
+                marker: PhantomData<T>,
+            }
+        );
+        assert_conversion!(
+            tokens.clone(),
+            "export type Id<T, U> = { id: number; value: U }"
+        );
+        assert_zod!(tokens, "export const IdSchema = <T extends z.ZodTypeAny, U extends z.ZodTypeAny>(TSchema: T, USchema: U) => z.object({ id: z.number(), value: USchema })\n");
+    }
+
+    #[test]
+    fn generic_phantom_guard() {
+        let tokens = quote!(
+            struct Id<T> {
+                id: u64,
+                marker: std::marker::PhantomData<T>,
+            }
+        );
+        assert_guard!(tokens, "export function isId<T>(obj: any, isT: (obj: any) => obj is T): obj is Id<T> {\n    return typeof obj === \"object\" && obj !== null && typeof obj.id === \"number\";\n}");
+    }
+
+    #[test]
+    fn generic_phantom_reference() {
+        // `Id<User, String>` must have the arity of `Id<T, U>`
+        let tokens = quote!(
+            struct Session {
+                user: Id<User, String>,
+            }
+        );
+        assert_conversion!(
+            tokens.clone(),
+            "export type Session = { user: Id<User, string> }"
+        );
+        assert_guard!(tokens.clone(), "export function isSession(obj: any): obj is Session {\n    return typeof obj === \"object\" && obj !== null && isId(obj.user, (e0: any): e0 is User => isUser(e0), (e0: any): e0 is string => typeof e0 === \"string\");\n}");
+        assert_zod!(tokens.clone(), "export const SessionSchema: z.ZodType<Session> = z.object({ user: z.lazy(() => IdSchema(z.lazy(() => UserSchema), z.string())) })\n");
+        assert_schema!(tokens, Dialect::JsonSchema, "{\"properties\":{\"user\":{\"$ref\":\"#/$defs/Id\"}},\"required\":[\"user\"],\"title\":\"Session\",\"type\":\"object\"}");
+    }
+
+    #[test]
+    fn generic_enum() {
+        let tokens = quote!(
+            #[serde(tag = "kind", content = "data")]
+            enum Event<T> {
+                Created(T),
+                Deleted { id: u64 },
+            }
+        );
+        assert_conversion!(tokens, "export type Event<T> =\n    | { kind: \"Created\"; data: T }\n    | { kind: \"Deleted\"; data: { id: number } }");
+    }
+
+    #[test]
+    fn generic_enum_factory() {
+        let tokens = quote!(
+            #[serde(tag = "kind", content = "data")]
+            enum Event<T> {
+                Created(T),
+                Deleted { id: u64 },
+            }
+        );
+        let factory = Typescriptify::new(tokens)
+            .parse()
+            .export_type_factory_source()
+            .unwrap();
+        assert_eq!(factory, "export const Event = Object.freeze({\n    Created<T>(content: T): Event<T> {\n        return { kind: \"Created\", data: content };\n    },\n    Deleted<T>(content: { id: number }): Event<T> {\n        return { kind: \"Deleted\", data: content };\n    },\n})\nexport const EventFactory = <R, T>(fn: (message: Event<T>) => R): EventFactoryReturnType<T, R> => Object.freeze({\n    Created(content: T): R {\n        return fn({ kind: \"Created\", data: content });\n    },\n    Deleted(content: { id: number }): R {\n        return fn({ kind: \"Deleted\", data: content });\n    },\n})\nexport type EventFactoryReturnType<T, R = void> = {\n    Created(content: T): R;\n    Deleted(content: { id: number }): R;\n}\n");
+    }
+
+    #[test]
+    fn generic_enum_handler() {
+        let tokens = quote!(
+            #[serde(tag = "kind", content = "data")]
+            enum Event<T> {
+                Created(T),
+                Deleted { id: u64 },
+            }
+        );
+        let handler = Typescriptify::new(tokens)
+            .parse()
+            .export_type_handler_source()
+            .unwrap();
+        assert_eq!(handler, "export interface HandleEvent<T> {\n    onCreated(message: T): any;\n    onDeleted(message: { id: number }): any;\n}\nexport namespace Event {\n    export type Created<T> = T\n    export type Deleted<T> = { id: number }\n}\nexport function applyEvent<T>(handler: HandleEvent<T>): (input: Event<T>) => any {\n    //@ts-ignore\n    return (input) => handler[\"on\" + input.kind](input.data);\n}\n");
+    }
+
+    #[test]
+    fn enum_style_union() {
+        let tokens = quote!(
+            #[ts(enum_style = "union")]
+            enum Color {
+                Red,
+                #[serde(rename = "green")]
+                Green,
+            }
+        );
+        let ts = Typescriptify::new(tokens).parse().type_script_ify_source();
+        assert_eq!(ts, "export type Color = \"Red\" | \"green\"\nexport const Color = (check: Color) => check\n");
+    }
+
+    #[test]
+    fn enum_style_const_enum() {
+        let tokens = quote!(
+            #[ts(enum_style = "const_enum")]
+            enum Color {
+                Red,
+                Green,
+            }
+        );
+        assert_conversion!(
+            tokens,
+            "export const enum Color { Red = \"Red\", Green = \"Green\" }"
+        );
+    }
+
+    #[test]
+    fn enum_style_const_object() {
+        let tokens = quote!(
+            #[ts(enum_style = "const_object")]
+            enum Color {
+                /// the first
+                Red,
+                Green,
+            }
+        );
+        let ts = Typescriptify::new(tokens).parse().type_script_ify_source();
+        assert_eq!(ts, "export type Color = (typeof Color)[keyof typeof Color]\nexport const Color = {\n    /**\n     * the first\n     */\n    Red: \"Red\",\n    Green: \"Green\",\n} as const\n");
+    }
+
+    #[test]
+    fn enum_style_const_object_input() {
+        let tokens = quote!(
+            #[ts(enum_style = "const_object")]
+            enum Color {
+                #[serde(rename(deserialize = "r"))]
+                Red,
+                Green,
+            }
+        );
+        assert_input!(tokens, "export type ColorInput = \"r\" | \"Green\"");
+    }
+
+    #[test]
+    fn enum_style_union_guard() {
+        let tokens = quote!(
+            #[ts(enum_style = "union")]
+            enum Color {
+                Red,
+                Green,
+            }
+        );
+        assert_guard!(tokens, "export function isColor(obj: any): obj is Color {\n    return obj === \"Red\" || obj === \"Green\";\n}");
+    }
+
+    #[test]
+    fn repr_enum() {
+        let tokens = quote!(
+            #[repr(i8)]
+            #[ts(repr)]
+            enum Level {
+                Low = -1,
+                /// the usual
+                Normal,
+                High = 0x10,
+                Higher,
+            }
+        );
+        assert_conversion!(tokens, "export enum Level {\n    Low = -1,\n    /**\n     * the usual\n     */\n    Normal = 0,\n    High = 16,\n    Higher = 17,\n}");
+    }
+
+    #[test]
+    fn repr_enum_guard() {
+        let tokens = quote!(
+            #[repr(u8)]
+            #[ts(repr)]
+            enum Level {
+                Low = 1,
+                High,
+            }
+        );
+        assert_guard!(
+            tokens,
+            "export function isLevel(obj: any): obj is Level {\n    return obj === 1 || obj === 2;\n}"
+        );
+    }
+
+    #[test]
+    fn serde_repr_enum() {
+        let tokens = quote!(
+            #[derive(Serialize_repr, Deserialize_repr)]
+            #[repr(u8)]
+            #[ts(repr, enum_style = "union")]
+            enum Level {
+                Low = 1,
+                High = 3,
+            }
+        );
+        assert_conversion!(tokens.clone(), "export type Level = 1 | 3");
+        assert_input!(tokens, "export type LevelInput = 1 | 3");
+    }
+
+    #[test]
+    fn repr_is_opt_in() {
+        let tokens = quote!(
+            #[repr(u8)]
+            enum Level {
+                Low = 1,
+                High = 3,
+            }
+        );
+        assert_conversion!(
+            tokens,
+            "export enum Level { Low = \"Low\", High = \"High\" }"
+        );
+    }
+
+    #[test]
+    fn int64() {
+        let tokens = quote!(
+            struct Ids {
+                count: u32,
+                total: u64,
+                #[ts(int64 = "string")]
+                id: u64,
+                #[ts(int64 = "bigint")]
+                ids: Vec<i128>,
+                #[ts(int64 = "bigint")]
+                names: HashMap<u64, usize>,
+            }
+        );
+        assert_conversion!(tokens, "export type Ids = { count: number; total: number; id: string; ids: bigint[]; names: Record<string, bigint> }");
+    }
+
+    #[test]
+    fn int64_guard() {
+        let tokens = quote!(
+            struct Ids {
+                #[ts(int64 = "string")]
+                id: u64,
+                #[ts(int64 = "bigint")]
+                ids: Option<i64>,
+            }
+        );
+        assert_guard!(tokens, "export function isIds(obj: any): obj is Ids {\n    return typeof obj === \"object\" && obj !== null && typeof obj.id === \"string\" && (obj.ids === null || typeof obj.ids === \"bigint\");\n}");
+    }
+
+    #[test]
+    fn int64_factory() {
+        let tokens = quote!(
+            #[serde(tag = "kind", content = "data")]
+            enum Event {
+                Created(#[ts(int64 = "string")] u64),
+            }
+        );
+        let factory = Typescriptify::new(tokens)
+            .parse()
+            .export_type_factory_source()
+            .unwrap();
+        assert!(
+            factory.contains("Created(content: string): Event"),
+            "{}",
+            factory
+        );
+    }
+
+    fn with_types(tokens: proc_macro2::TokenStream, types: &str) -> Typescriptify {
+        Typescriptify::with_config(tokens, std::sync::Arc::new(Config::parse(types).unwrap()))
+    }
+
+    #[test]
+    fn config_types() {
+        let tokens = quote!(
+            struct User {
+                id: uuid::Uuid,
+                balance: Money,
+                other: other::Decimal,
+                tags: Vec<Tag>,
+            }
+        );
+        let tsy = with_types(
+            tokens,
+            r#"
+            [types]
+            Uuid = "string"
+            "bank::Money" = "string"
+            "other::Decimal" = "{ value: number }"
+            "#,
+        );
+        let parsed = tsy.parse();
+        assert_eq!(parsed.export_type_definition_source().declarations, "export type User = { id: string; balance: Money; other: { value: number }; tags: Tag[] }");
+        assert_eq!(parsed.dependencies(), &["Money", "Tag"]);
+    }
+
+    #[test]
+    fn config_types_win() {
+        // e.g. with time's `serde-human-readable` feature
+        let tokens = quote!(
+            struct S {
+                at: time::OffsetDateTime,
+            }
+        );
+        let tsy = with_types(tokens, "[types]\nOffsetDateTime = \"string\"");
+        assert_eq!(
+            tsy.parse().export_type_definition_source().declarations,
+            "export type S = { at: string }"
+        );
+    }
+
+    #[test]
+    fn config_types_guard() {
+        let tokens = quote!(
+            struct User {
+                id: Uuid,
+                other: other::Decimal,
+            }
+        );
+        let tsy = with_types(
+            tokens,
+            r#"
+            [types]
+            Uuid = "string"
+            "other::Decimal" = "{ value: number }"
+            "#,
+        );
+        assert_eq!(tsy.parse().export_type_guard_source(), "export function isUser(obj: any): obj is User {\n    return typeof obj === \"object\" && obj !== null && typeof obj.id === \"string\" && typeof obj.other === \"object\" && obj.other !== null && typeof obj.other.value === \"number\";\n}");
+    }
+
+    #[test]
+    fn opaque_names_guard() {
+        let tokens = quote!(
+            struct User<T> {
+                id: Uuid,
+                #[ts(ts_type = "Date")]
+                at: Time,
+                #[ts(ts_type = "T[]")]
+                list: Vec<T>,
+            }
+        );
+        let tsy = with_types(tokens, "[types]\nUuid = \"UUID\"");
+        // there is no `isUUID` or `isDate`
+        assert_eq!(tsy.parse().export_type_guard_source(), "export function isUser<T>(obj: any, isT: (obj: any) => obj is T): obj is User<T> {\n    return typeof obj === \"object\" && obj !== null && Array.isArray(obj.list) && obj.list.every((e0: any) => isT(e0));\n}");
+    }
+
+    #[test]
+    fn json_value() {
+        let tokens = quote!(
+            #[derive(Serialize, Deserialize)]
+            #[ts(json_style = "json_value")]
+            struct Event {
+                payload: serde_json::Value,
+                extra: serde_json::Map<String, serde_json::Value>,
+                raw: Box<serde_json::value::RawValue>,
+                borrow: Value<i32>,
+            }
+        );
+        let tsy = Typescriptify::new(tokens);
+        let parsed = tsy.parse();
+        assert_eq!(parsed.export_type_definition_source().declarations, "export type Event = { payload: JsonValue; extra: Record<string, JsonValue>; raw: JsonValue; borrow: Value<number> }");
+        assert_eq!(parsed.dependencies(), &["Value", "JsonValue"]);
+        assert_eq!(tsy.parse_input().export_type_declaration_source(), "export type EventInput = { payload: JsonValue; extra: Record<string, JsonValue>; raw: JsonValue; borrow: ValueInput<number> }");
+    }
+
+    #[test]
+    fn own_value_type() {
+        // not `serde_json::Value`
+        let tokens = quote!(
+            struct Reading {
+                value: Value,
+                raw: Box<RawValue>,
+            }
+        );
+        let parsed = Typescriptify::new(tokens).parse();
+        assert_eq!(
+            parsed.export_type_definition_source().declarations,
+            "export type Reading = { value: Value; raw: RawValue }"
+        );
+        assert_eq!(parsed.dependencies(), &["Value", "RawValue"]);
+    }
+
+    #[test]
+    fn json_value_from_config() {
+        let tokens = quote!(
+            struct Event {
+                payload: Value,
+                raw: Box<RawValue>,
+            }
+        );
+        let tsy = with_types(
+            tokens,
+            r#"
+            [types]
+            Value = "JsonValue"
+            RawValue = "JsonValue"
+            "#,
+        );
+        let parsed = tsy.parse();
+        assert_eq!(
+            parsed.export_type_definition_source().declarations,
+            "export type Event = { payload: JsonValue; raw: JsonValue }"
+        );
+        assert_eq!(parsed.dependencies(), &["JsonValue"]);
+        assert_eq!(parsed.export_type_guard_source(), "export function isEvent(obj: any): obj is Event {\n    return typeof obj === \"object\" && obj !== null && obj.payload !== undefined && obj.raw !== undefined;\n}");
+    }
+
+    #[test]
+    fn json_value_unknown() {
+        let tokens = quote!(
+            #[ts(json_style = "unknown")]
+            struct Event {
+                payload: serde_json::value::Value,
+                extra: serde_json::Map<String, serde_json::Value>,
+            }
+        );
+        let parsed = Typescriptify::new(tokens).parse();
+        assert_eq!(
+            parsed.export_type_definition_source().declarations,
+            "export type Event = { payload: unknown; extra: Record<string, unknown> }"
+        );
+        assert!(parsed.dependencies().is_empty());
+        assert_eq!(parsed.export_type_guard_source(), "export function isEvent(obj: any): obj is Event {\n    return typeof obj === \"object\" && obj !== null && obj.payload !== undefined && typeof obj.extra === \"object\" && obj.extra !== null;\n}");
+    }
+
+    #[test]
+    fn bad_json_style() {
+        let tokens = quote!(
+            #[ts(json_style = "any")]
+            struct Event {
+                payload: Value,
+            }
+        );
+        let result = std::panic::catch_unwind(move || drop(Typescriptify::new(tokens).parse()));
+        match result {
+            Ok(_) => panic!("expecting panic!"),
+            Err(ref msg) => assert!(msg
+                .downcast_ref::<String>()
+                .unwrap()
+                .contains("is not one of \\\"json_value\\\" or \\\"unknown\\\"")),
+        }
+    }
+
+    #[test]
+    fn strict() {
+        let tokens = quote!(
+            #[ts(strict)]
+            struct S {
+                a: HashMap<String, Vec<i32>>,
+                b: BTreeMap<u32, Option<Point>>,
+                c: HashMap<Point, i32>,
+                #[ts(ts_type = "() => void")]
+                d: Box<dyn Fn()>,
+            }
+        );
+        let parsed = Typescriptify::new(tokens).parse();
+        assert_eq!(parsed.export_type_definition_source().declarations, "export type S = { a: Record<string, number[]>; b: Record<number, Point | null>; c: Partial<Record<Point, number>>; d: () => void }");
+    }
+
+    #[test]
+    fn strict_errors() {
+        let cases = vec![
+            (quote!(a: my_macro!()), "it would be `any`"),
+            (
+                quote!(a: Box<dyn Fn(i32) -> i32>),
+                "can't serialize a closure",
+            ),
+            (
+                quote!(a: Box<dyn Display>),
+                "a trait has no typescript type",
+            ),
+            (quote!(a: *const u8), "can't serialize a raw pointer"),
+            (
+                quote!(a: HashMap<(i32, i32), String>),
+                "can't serialize a map with this key",
+            ),
+            (
+                quote!(a: BTreeMap<Vec<u8>, String>),
+                "can't serialize a map with this key",
+            ),
+        ];
+        for (field, msg) in cases {
+            let lax = quote!(#[ts(strict = false)] struct S { #field });
+            drop(Typescriptify::new(lax).parse());
+            let tokens = quote!(#[ts(strict)] struct S { #field });
+            let result = std::panic::catch_unwind(move || drop(Typescriptify::new(tokens).parse()));
+            match result {
+                Ok(_) => panic!("expecting panic for {}", msg),
+                Err(ref err) => {
+                    let err = err.downcast_ref::<String>().unwrap();
+                    assert!(err.contains("strict: ") && err.contains(msg), "{}", err)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn map_style() {
+        let tokens = quote!(
+            struct S {
+                a: HashMap<Color, i32>,
+                b: BTreeMap<u32, String>,
+                #[ts(map_style = "index")]
+                c: HashMap<String, i32>,
+                #[ts(map_style = "es_map")]
+                d: HashMap<u32, Color>,
+                #[ts(map_style = "es_map", readonly)]
+                e: HashMap<String, i32>,
+            }
+        );
+        assert_conversion!(tokens.clone(), "export type S = { a: Partial<Record<Color, number>>; b: Record<number, string>; c: { [key: string]: number }; d: Map<number, Color>; readonly e: ReadonlyMap<string, number> }");
+        assert_guard!(tokens, "export function isS(obj: any): obj is S {\n    return typeof obj === \"object\" && obj !== null && typeof obj.a === \"object\" && obj.a !== null && Object.entries(obj.a).every(([k0, v0]) => isColor(k0) && typeof v0 === \"number\") && typeof obj.b === \"object\" && obj.b !== null && Object.entries(obj.b).every(([k0, v0]) => !isNaN(+k0) && typeof v0 === \"string\") && typeof obj.c === \"object\" && obj.c !== null && Object.values(obj.c).every((v0) => typeof v0 === \"number\") && obj.d instanceof Map && Array.from(obj.d).every(([k0, v0]) => typeof k0 === \"number\" && isColor(v0)) && obj.e instanceof Map && Array.from(obj.e).every(([k0, v0]) => typeof k0 === \"string\" && typeof v0 === \"number\");\n}");
+
+        let tokens = quote!(
+            #[ts(map_style = "index")]
+            struct S {
+                a: HashMap<String, i32>,
+            }
+        );
+        assert_conversion!(tokens, "export type S = { a: { [key: string]: number } }");
+
+        // a type parameter isn't known to be a `PropertyKey`
+        let tokens = quote!(
+            struct S<K> {
+                m: HashMap<K, u8>,
+            }
+        );
+        assert_conversion!(
+            tokens.clone(),
+            "export type S<K> = { m: Record<string, number> }"
+        );
+        assert_guard!(tokens.clone(), "export function isS<K>(obj: any, isK: (obj: any) => obj is K): obj is S<K> {\n    return typeof obj === \"object\" && obj !== null && typeof obj.m === \"object\" && obj.m !== null && Object.values(obj.m).every((v0) => typeof v0 === \"number\");\n}");
+        assert_zod!(tokens, "export const SSchema = <K extends z.ZodTypeAny>(KSchema: K) => z.object({ m: z.record(z.string(), z.number()) })\n");
+
+        let tokens = quote!(
+            #[ts(map_style = "hash")]
+            struct S {
+                a: HashMap<String, i32>,
+            }
+        );
+        let result = std::panic::catch_unwind(move || drop(Typescriptify::new(tokens).parse()));
+        match result {
+            Ok(_) => panic!("expecting panic!"),
+            Err(ref msg) => assert!(msg
+                .downcast_ref::<String>()
+                .unwrap()
+                .contains("is not one of \\\"record\\\", \\\"index\\\" or \\\"es_map\\\"")),
+        }
+    }
+
+    #[test]
+    fn profile() {
+        let fields = quote!(
+            a: HashMap<String, u64>,
+            b: Option<i32>,
+            #[serde(with = "serde_bytes")]
+            c: Vec<u8>,
+            d: Vec<u8>,
+            e: ByteBuf,
+        );
+        assert_conversion!(
+            quote!(struct S { #fields }),
+            "export type S = { a: Record<string, number>; b: number | null; c: number[]; d: number[]; e: number[] }"
+        );
+        assert_conversion!(
+            quote!(#[ts(profile = "wasm_bindgen")] struct S { #fields }),
+            "export type S = { a: Map<string, bigint>; b: number | undefined; c: Uint8Array; d: number[]; e: Uint8Array }"
+        );
+        assert_guard!(quote!(#[ts(profile = "wasm_bindgen")] struct S { #fields }), "export function isS(obj: any): obj is S {\n    return typeof obj === \"object\" && obj !== null && obj.a instanceof Map && Array.from(obj.a).every(([k0, v0]) => typeof k0 === \"string\" && typeof v0 === \"bigint\") && (obj.b === undefined || typeof obj.b === \"number\") && obj.c instanceof Uint8Array && Array.isArray(obj.d) && obj.d.every((e0: any) => typeof e0 === \"number\") && obj.e instanceof Uint8Array;\n}");
+        assert_conversion!(
+            quote!(#[ts(profile = "msgpack")] struct S { #fields }),
+            "export type S = { a: Record<string, number>; b: number | null; c: Uint8Array; d: number[]; e: Uint8Array }"
+        );
+        // explicit attributes still win
+        assert_conversion!(
+            quote!(
+                #[ts(profile = "wasm_bindgen", map_style = "record", option_style = "null")]
+                struct S {
+                    #[ts(int64 = "number")]
+                    a: HashMap<String, u64>,
+                    b: Option<i32>,
+                }
+            ),
+            "export type S = { a: Record<string, number>; b: number | null }"
+        );
+
+        assert_conversion!(
+            quote!(
+                struct Unit;
+            ),
+            "export type Unit = null"
+        );
+        let tokens = quote!(
+            #[ts(profile = "wasm_bindgen")]
+            struct Unit;
+        );
+        assert_conversion!(tokens.clone(), "export type Unit = undefined");
+        assert_guard!(
+            tokens,
+            "export function isUnit(obj: any): obj is Unit {\n    return obj === undefined;\n}"
+        );
+        let tokens = quote!(
+            #[ts(profile = "msgpack")]
+            struct Unit;
+        );
+        assert_conversion!(tokens.clone(), "export type Unit = []");
+        assert_guard!(
+            tokens,
+            "export function isUnit(obj: any): obj is Unit {\n    return Array.isArray(obj) && obj.length === 0;\n}"
+        );
+    }
+
+    #[test]
+    fn readonly() {
+        let tokens = quote!(
+            struct S {
+                #[ts(readonly)]
+                a: Vec<i32>,
+                b: Vec<i32>,
+            }
+        );
+        let parsed = Typescriptify::new(tokens).parse();
+        assert_eq!(
+            parsed.export_type_definition_source().declarations,
+            "export type S = { readonly a: readonly number[]; b: number[] }"
+        );
+
+        let tokens = quote!(
+            #[ts(readonly)]
+            struct S {
+                a: HashMap<String, i32>,
+                b: Vec<Vec<i32>>,
+                c: HashSet<String>,
+                #[ts(readonly = false)]
+                d: Vec<i32>,
+                #[ts(ts_type = "readonly string[]")]
+                e: Strings,
+            }
+        );
+        let parsed = Typescriptify::new(tokens).parse();
+        assert_eq!(parsed.export_type_definition_source().declarations, "export type S = { readonly a: Readonly<Record<string, number>>; readonly b: readonly (readonly number[])[]; readonly c: readonly string[]; d: number[]; readonly e: readonly string[] }");
+
+        let tokens = quote!(
+            #[ts(readonly)]
+            #[serde(tag = "kind", content = "data")]
+            enum E {
+                A(Vec<i32>),
+                B { id: u32 },
+            }
+        );
+        let parsed = Typescriptify::new(tokens).parse();
+        assert_eq!(parsed.export_type_definition_source().declarations, "export type E =\n    | { readonly kind: \"A\"; readonly data: readonly number[] }\n    | { readonly kind: \"B\"; readonly data: { readonly id: number } }");
+    }
+
+    #[test]
+    fn config_errors() {
+        assert_eq!(
+            Config::parse("[types]\nUuid = 1").unwrap_err(),
+            "types.Uuid: must be a typescript type string"
+        );
+        assert!(Config::parse("[types").is_err());
+        assert_eq!(
+            Config::parse("[defaults]\nenum_style = \"nope\"").unwrap_err(),
+            "defaults.enum_style: \"nope\" is not one of \"enum\", \"union\", \"const_enum\" or \"const_object\""
+        );
+        assert_eq!(
+            Config::parse("[defaults]\nstrict = \"yes\"").unwrap_err(),
+            "defaults.strict: must be true or false"
+        );
+        assert_eq!(
+            Config::parse("[defaults]\nenum_stile = \"union\"").unwrap_err(),
+            "defaults.enum_stile: unknown setting"
+        );
+        assert_eq!(
+            Config::parse("[defaults]\nmap_style = \"hash\"").unwrap_err(),
+            "defaults.map_style: \"hash\" is not one of \"record\", \"index\" or \"es_map\""
+        );
+        assert_eq!(
+            Config::parse("[outputs]\nguard = 1").unwrap_err(),
+            "outputs.guard: must be true or false"
+        );
+        assert_eq!(
+            Config::parse("[outputs]\nguards = true").unwrap_err(),
+            "outputs.guards: unknown output"
+        );
+    }
+
+    #[test]
+    fn config_reloads() {
+        let dir = std::env::temp_dir().join(format!("tsy-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("typescript-definitions.toml");
+        std::fs::write(&path, "[outputs]\nzod = false").unwrap();
+        assert!(!Config::load_dir(&dir).unwrap().outputs.zod);
+        // a long lived proc macro server sees the edit
+        std::fs::write(&path, "[outputs]\nzod = true").unwrap();
+        let later = std::time::SystemTime::now() + std::time::Duration::from_secs(10);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert!(Config::load_dir(&dir).unwrap().outputs.zod);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn config_outputs() {
+        let config = Config::parse("[outputs]\nzod = false").unwrap();
+        assert!(config.outputs.guard && config.outputs.json_schema);
+        assert!(!config.outputs.zod);
+        let config = Config::parse("").unwrap();
+        assert!(config.outputs.guard && config.outputs.zod && config.outputs.json_schema);
+    }
+
+    #[test]
+    fn config_defaults() {
+        let tokens = quote!(
+            enum Color {
+                Red,
+                Green,
+            }
+        );
+        let config = r#"
+            [defaults]
+            enum_style = "union"
+            int64 = "string"
+            strict = true
+            readonly = true
+            option_style = "optional"
+            map_style = "index"
+            "#;
+        let tsy = with_types(tokens, config);
+        assert_eq!(
+            tsy.parse().export_type_definition_source().declarations,
+            "export type Color = \"Red\" | \"Green\""
+        );
+        // the attribute still wins
+        let tokens = quote!(
+            #[ts(enum_style = "enum")]
+            enum Color {
+                Red,
+                Green,
+            }
+        );
+        let tsy = with_types(tokens, config);
+        assert_eq!(
+            tsy.parse().export_type_definition_source().declarations,
+            "export enum Color { Red = \"Red\", Green = \"Green\" }"
+        );
+        let tokens = quote!(
+            struct S {
+                a: u64,
+                #[ts(int64 = "bigint")]
+                b: i128,
+                #[ts(readonly = false)]
+                c: Vec<i32>,
+                #[ts(readonly = false)]
+                d: Option<u8>,
+                #[ts(readonly = false)]
+                e: HashMap<String, u8>,
+            }
+        );
+        let tsy = with_types(tokens, config);
+        assert_eq!(
+            tsy.parse().export_type_definition_source().declarations,
+            "export type S = { readonly a: string; readonly b: bigint; c: number[]; d?: number; e: { [key: string]: number } }"
+        );
+        let tokens = quote!(
+            struct S {
+                f: Box<dyn Fn()>,
+            }
+        );
+        let mut parsed = with_types(tokens.clone(), config).parse();
+        assert!(parsed.check().is_err());
+        let tokens = quote!(
+            #[ts(strict = false)]
+            #tokens
+        );
+        let mut parsed = with_types(tokens, config).parse();
+        assert!(parsed.check().is_ok());
+        let tokens = quote!(
+            struct S {
+                a: Option<u64>,
+            }
+        );
+        let tsy = with_types(tokens, "[defaults]\nprofile = \"wasm_bindgen\"");
+        assert_eq!(
+            tsy.parse().export_type_definition_source().declarations,
+            "export type S = { a: bigint | undefined }"
+        );
+        let tokens = quote!(
+            struct S {
+                a: serde_json::Value,
+            }
+        );
+        let tsy = with_types(tokens, "[defaults]\njson_style = \"unknown\"");
+        let parsed = tsy.parse();
+        assert_eq!(
+            parsed.export_type_definition_source().declarations,
+            "export type S = { a: unknown }"
+        );
+        assert!(parsed.dependencies().is_empty());
+    }
+
+    #[cfg(all(
+        feature = "arrayvec-impl",
+        feature = "bytes-impl",
+        feature = "chrono-impl",
+        feature = "indexmap-impl",
+        feature = "ordered-float-impl",
+        feature = "rust-decimal-impl",
+        feature = "smallvec-impl",
+        feature = "time-impl",
+        feature = "url-impl",
+        feature = "uuid-impl"
+    ))]
+    #[test]
+    fn ecosystem_types() {
+        let tokens = quote!(
+            struct S {
+                id: Uuid,
+                date: NaiveDate,
+                at: chrono::NaiveDateTime,
+                elapsed: chrono::Duration,
+                timeout: Duration,
+                offset: time::OffsetDateTime,
+                local: PrimitiveDateTime,
+                day: time::Date,
+                clock: time::Time,
+                zone: UtcOffset,
+                wait: time::Duration,
+                url: url::Url,
+                price: Decimal,
+                index: IndexMap<String, u32>,
+                set: IndexSet<i32>,
+                small: SmallVec<[u8; 4]>,
+                array: ArrayVec<String, 3>,
+                old_array: ArrayVec<[f64; 2]>,
+                body: bytes::Bytes,
+                weight: OrderedFloat<f32>,
+            }
+        );
+        let parsed = Typescriptify::new(tokens).parse();
+        assert_eq!(parsed.export_type_definition_source().declarations, "export type S = { id: string; date: string; at: string; elapsed: [number, number]; timeout: { secs: number; nanos: number }; offset: [number, number, number, number, number, number, number, number, number]; local: [number, number, number, number, number, number]; day: [number, number]; clock: [number, number, number, number]; zone: [number, number, number]; wait: [number, number]; url: string; price: string; index: Record<string, number>; set: number[]; small: number[]; array: string[]; old_array: number[]; body: number[]; weight: number }");
+    }
+
+    #[cfg(all(
+        feature = "chrono-impl",
+        feature = "indexmap-impl",
+        feature = "uuid-impl"
+    ))]
+    #[test]
+    fn ecosystem_types_guard() {
+        let tokens = quote!(
+            struct S {
+                id: Uuid,
+                elapsed: chrono::Duration,
+                index: IndexMap<String, u32>,
+            }
+        );
+        assert_eq!(Typescriptify::new(tokens).parse().export_type_guard_source(), "export function isS(obj: any): obj is S {\n    return typeof obj === \"object\" && obj !== null && typeof obj.id === \"string\" && Array.isArray(obj.elapsed) && obj.elapsed.length === 2 && typeof obj.elapsed[0] === \"number\" && typeof obj.elapsed[1] === \"number\" && typeof obj.index === \"object\" && obj.index !== null && Object.values(obj.index).every((v0) => typeof v0 === \"number\");\n}");
+    }
+
+    // Error tests
+
+    #[test]
+    fn conversion_is_only_valid_for_structs_or_enums() {
+        let tokens = quote!(type Foo(String));
+
+        let result = std::panic::catch_unwind(move || Typescriptify::new(tokens).parse());
+        match result {
+            Ok(_) => panic!("expecting panic!"),
+            Err(ref msg) => assert!(msg
+                .downcast_ref::<String>()
+                .unwrap()
+                .contains("expected one of: `struct`, `enum`, `union`")),
+        }
+    }
+
+    #[test]
+    fn ts_repr_needs_repr() {
+        let tokens = quote!(
+            #[ts(repr)]
+            enum Level {
+                Low = 1,
+            }
+        );
+
+        let result = std::panic::catch_unwind(move || drop(Typescriptify::new(tokens).parse()));
+        match result {
+            Ok(_) => panic!("expecting panic!"),
+            Err(ref msg) => assert!(msg
+                .downcast_ref::<String>()
+                .unwrap()
+                .contains("needs a #[repr(..)] attribute")),
+        }
+    }
+
+    #[test]
+    fn bound_must_name_a_type_parameter() {
+        let tokens = quote!(
+            #[ts(bound = "U: string")]
+            struct S<T> {
+                a: T,
+            }
+        );
+
+        // errors are reported when the parse context is dropped
+        let result = std::panic::catch_unwind(move || drop(Typescriptify::new(tokens).parse()));
+        match result {
+            Ok(_) => panic!("expecting panic!"),
+            Err(ref msg) => assert!(msg
+                .downcast_ref::<String>()
+                .unwrap()
+                .contains("is not a type parameter")),
+        }
+    }
+
+    // TODO: None of these compiled at all previously, nevermind passing, and it's less bang for buck fixing it than getting the type conversion test coverage.
+    // We should probably bring them back in future though.
+
+    // #[test]
+    // fn tag_clash_in_enum() {
+    //     let tokens = quote!(
+    //         #[derive(Serialize)]
+    //         #[serde(tag = "kind")]
+    //         enum A {
+    //             Unit,
+    //             B { kind: i32, b: String },
+    //         }
+    //     );
+
+    //     let result = std::panic::catch_unwind(move || Typescriptify::new(tokens).parse());
+    //     match result {
+    //         Ok(_x) => assert!(false, "expecting panic!"),
+    //         Err(ref msg) => assert_snapshot_matches!( msg.downcast_ref::<String>().unwrap(),
+    //         @r###"2 errors:
+    // # variant field name `kind` conflicts with internal tag
+    // # clash with field in "A::B". Maybe use a #[serde(content="...")] attribute."###
+    //         ),
+    //     }
+    // }
+    // #[test]
+    // fn verify_is_recognized() {
+    //     let tokens = quote!(
+    //         #[derive(Serialize)]
+    //         #[ts(guard = "blah")]
+    //         struct S {
+    //             a: i32,
+    //             b: f64,
+    //         }
+    //     );
+    //     let result = std::panic::catch_unwind(move || Typescriptify::new(tokens).parse_verify());
+    //     match result {
+    //         Ok(_x) => assert!(false, "expecting panic!"),
+    //         Err(ref msg) => assert_snapshot_matches!( msg.downcast_ref::<String>().unwrap(),
+    //         @r###"S: guard must be true or false not ""blah"""###
+    //         ),
+    //     }
+    // }
+    // #[test]
+    // fn turbofish() {
+    //     let tokens = quote!(
+    //         #[derive(TypeScriptify)]
+    //         #[ts(turbofish = "<i32>")]
+    //         struct S<T> {
+    //             a: i32,
+    //             b: Vec<T>,
+    //         }
+    //     );
+    //     let ty = Typescriptify::parse(true, tokens);
+    //     let i = &ty.ctxt.ident;
+    //     let g = ty.ctxt.global_attrs.turbofish.unwrap_or_else(|| quote!());
+    //     let res = quote!(#i#g::type_script_ify()).to_string();
+    //     assert_snapshot_matches!(res,
+    //     @"S < i32 > :: type_script_ify ( )" );
+    // }
+    // #[test]
+    // fn bad_ts_as() {
+    //     let tokens = quote!(
+    //         #[derive(TypeScriptify)]
+
+    //         struct S<T> {
+    //             #[ts(ts_as = "😀i32>")]
+    //             a: i32,
+    //             #[ts(ts_as = "T[]")]
+    //             b: Vec<T>,
+    //         }
+    //     );
+    //     let result = std::panic::catch_unwind(move || Typescriptify::new(tokens).parse(true));
+    //     match result {
+    //         Ok(_x) => assert!(false, "expecting panic!"),
+    //         Err(ref msg) => assert_snapshot_matches!( msg.downcast_ref::<String>().unwrap(),
+    //         @r###"2 errors:
+    // # ts_as: "😀i32>" is not a valid rust type
+    // # ts_as: "T[]" is not a valid rust type"###
+    //         ),
+    //     }
+    // }
+}
@@ -0,0 +1,315 @@
+// Copyright 2019 Ian Castleden
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+use super::attrs::{Int64, JsonStyle, MapStyle, OptionStyle};
+use super::ts::{Member, Param, Type};
+use super::{
+    ecosystem_type, ident_from_str, is_byte_buffer, is_bytes, is_composite, is_int64, is_json_map,
+    is_json_value, last_path_element, FieldContext, TSType, JSON_VALUE,
+};
+
+/// `{ name: type; ... }`
+fn object(members: &[(&str, Type)], readonly: bool) -> Type {
+    Type::Object(
+        members
+            .iter()
+            .map(|(key, ty)| Member::Property {
+                docs: vec![],
+                key: key.to_string(),
+                optional: false,
+                readonly,
+                ty: ty.clone(),
+            })
+            .collect(),
+    )
+}
+
+/// `{ [key: K]: V }`
+fn map(key: Type, value: Type) -> Type {
+    Type::Object(vec![Member::Index { key, value }])
+}
+
+/// `Record<K, V>`, or `Partial<Record<K, V>>` unless `K` is `string` or `number`
+fn record(key: Type, value: Type) -> Type {
+    let every_key = key == Type::named("string") || key == Type::named("number");
+    let record = Type::Ref("Record".into(), vec![key, value]);
+    if every_key {
+        record
+    } else {
+        Type::Ref("Partial".into(), vec![record])
+    }
+}
+
+impl<'a> FieldContext<'a> {
+    /// `T[]`, or `readonly T[]`
+    fn array_type(&self, elem: Type) -> Type {
+        if self.readonly() {
+            Type::Readonly(Box::new(Type::array(elem)))
+        } else {
+            Type::array(elem)
+        }
+    }
+
+    /// `[T, T, ...]` for a `[T; N]` array, or `readonly [T, T, ...]`
+    fn fixed_array_type(&self, elem: Type, len: usize) -> Type {
+        let tuple = Type::Tuple(vec![elem; len]);
+        if self.readonly() {
+            Type::Readonly(Box::new(tuple))
+        } else {
+            tuple
+        }
+    }
+
+    /// `Record<K, V>`, `{ [key: K]: V }` or `Map<K, V>` depending on the `map_style`
+    /// (and `Readonly<..>` or `ReadonlyMap<K, V>`)
+    fn map_type(&self, key: Type, value: Type) -> Type {
+        let ty = match self.map_style() {
+            MapStyle::Record => record(key, value),
+            MapStyle::Index => map(key, value),
+            MapStyle::EsMap if self.readonly() => {
+                return Type::Ref("ReadonlyMap".into(), vec![key, value])
+            }
+            MapStyle::EsMap => return Type::Ref("Map".into(), vec![key, value]),
+        };
+        if self.readonly() {
+            Type::Ref("Readonly".into(), vec![ty])
+        } else {
+            ty
+        }
+    }
+
+    /// `serde_json::Value`: `JsonValue` (which we then depend on) or `unknown`
+    fn json_value_to_ts(&self) -> Type {
+        match self.ctxt.json_style() {
+            JsonStyle::JsonValue => {
+                self.ctxt.add_dependency(&ident_from_str(JSON_VALUE));
+                Type::named(JSON_VALUE)
+            }
+            JsonStyle::Unknown => Type::named("unknown"),
+        }
+    }
+
+    #[allow(clippy::cognitive_complexity)]
+    fn generic_to_ts(&self, ts: &TSType) -> Type {
+        let to_ts = |ty: &syn::Type| self.type_to_ts(ty);
+        // typescript-definitions.toml [types]
+        if let Some(ts_type) = self.ctxt.config.lookup(&ts.path()) {
+            // e.g. `Value = "JsonValue"` for a `use serde_json::Value`
+            if ts_type == JSON_VALUE {
+                return self.json_value_to_ts();
+            }
+            return Type::from_source(ts_type);
+        }
+        if is_byte_buffer(ts) {
+            if self.ctxt.binary_bytes() {
+                return Type::named("Uint8Array");
+            }
+            return to_ts(&syn::parse_quote!(Vec<u8>));
+        }
+        if let Some(ty) = ecosystem_type(ts) {
+            return to_ts(&ty);
+        }
+        let name = ts.ident.to_string();
+        match name.as_ref() {
+            _ if is_json_value(ts) => self.json_value_to_ts(),
+            _ if is_json_map(ts) => self.map_type(Type::named("string"), self.json_value_to_ts()),
+            n if is_int64(n) => match self.int64() {
+                Int64::Number => Type::named("number"),
+                Int64::Bigint => Type::named("bigint"),
+                Int64::String => Type::named("string"),
+            },
+            "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "i8" | "i16" | "i32" | "i64"
+            | "i128" | "isize" | "f64" | "f32" => Type::named("number"),
+            "String" | "str" | "char" | "Path" | "PathBuf" => Type::named("string"),
+            "bool" => Type::named("boolean"),
+            "Box" | "Cow" | "Rc" | "Arc" | "Cell" | "RefCell" if ts.args.len() == 1 => {
+                to_ts(&ts.args[0])
+            }
+            "Duration" => object(
+                &[
+                    ("secs", Type::named("number")),
+                    ("nanos", Type::named("number")),
+                ],
+                self.readonly(),
+            ),
+            "SystemTime" => object(
+                &[
+                    ("secs_since_epoch", Type::named("number")),
+                    ("nanos_since_epoch", Type::named("number")),
+                ],
+                self.readonly(),
+            ),
+            // std::collections
+            "Vec" | "VecDeque" | "LinkedList" if ts.args.len() == 1 => {
+                self.type_to_array(&ts.args[0])
+            }
+            "HashMap" | "BTreeMap" if ts.args.len() == 2 => {
+                // a javascript `Map` takes any key
+                if is_composite(&ts.args[0]) && self.map_style() != MapStyle::EsMap {
+                    self.strict_error(
+                        &ts.args[0],
+                        "serde_json can't serialize a map with this key",
+                    );
+                }
+                self.map_type(self.key_to_ts(&ts.args[0]), to_ts(&ts.args[1]))
+            }
+            "HashSet" | "BTreeSet" if ts.args.len() == 1 => self.array_type(to_ts(&ts.args[0])),
+            "Option" if ts.args.len() == 1 => {
+                let none = match self.ctxt.option_style() {
+                    OptionStyle::Undefined => "undefined",
+                    _ => "null",
+                };
+                Type::union(vec![to_ts(&ts.args[0]), Type::named(none)])
+            }
+            "Result" if ts.args.len() == 2 => Type::union(vec![
+                object(&[("Ok", to_ts(&ts.args[0]))], self.readonly()),
+                object(&[("Err", to_ts(&ts.args[1]))], self.readonly()),
+            ]),
+            "Either" if ts.args.len() == 2 => Type::union(vec![
+                object(&[("Left", to_ts(&ts.args[0]))], self.readonly()),
+                object(&[("Right", to_ts(&ts.args[1]))], self.readonly()),
+            ]),
+            "Fn" | "FnOnce" | "FnMut" => {
+                self.strict_error(&ts.ident, "serde can't serialize a closure");
+                let params = ts
+                    .args
+                    .iter()
+                    .enumerate()
+                    .map(|(i, ty)| Param::new(format!("arg{}", i), to_ts(ty)))
+                    .collect();
+                let ret = match ts.return_type {
+                    Some(ref rt) => to_ts(rt),
+                    None => Type::named("undefined"),
+                };
+                Type::Function(params, Box::new(ret))
+            }
+            _ => {
+                let owned = ts.path();
+                let path: Vec<&str> = owned.iter().map(|s| s.as_ref()).collect();
+                match path[..] {
+                    ["chrono", "DateTime"] => Type::named("string"),
+                    _ => {
+                        self.ctxt.add_dependency(&ts.ident);
+                        Type::Ref(
+                            self.ctxt.reference_name(&ts.ident),
+                            self.derive_syn_types(&ts.args),
+                        )
+                    }
+                }
+            }
+        }
+    }
+
+    /// in strict mode a conversion that loses the type is an error
+    fn strict_error<A: quote::ToTokens>(&self, tokens: A, msg: &str) {
+        if self.ctxt.strict() {
+            self.ctxt.err_msg(tokens, &format!("strict: {}", msg));
+        }
+    }
+
+    /// the type of a map key: typescript doesn't allow `[key: bigint]`
+    fn key_to_ts(&self, ty: &syn::Type) -> Type {
+        match self.get_path(ty) {
+            // a `Map` keeps the keys as they are
+            _ if self.map_style() == MapStyle::EsMap => self.type_to_ts(ty),
+            // `Record<K, V>` needs `K extends PropertyKey`: serde writes the keys as strings
+            Some(ref ts) if self.ctxt.is_type_param(ts.ident.to_string()) => Type::named("string"),
+            Some(ref ts) if is_int64(&ts.ident.to_string()) && self.int64() == Int64::Bigint => {
+                Type::named("string")
+            }
+            _ => self.type_to_ts(ty),
+        }
+    }
+
+    fn type_to_array(&self, elem: &syn::Type) -> Type {
+        // check for [u8] or Vec<u8>
+
+        if let Some(ty) = self.get_path(elem) {
+            if ty.ident == "u8" && matches!(self.field, Some(f) if is_bytes(f)) {
+                return Type::named("string");
+            };
+            if ty.ident == "u8" && self.is_byte_array() {
+                return Type::named("Uint8Array");
+            };
+        };
+
+        self.array_type(self.type_to_ts(elem))
+    }
+    /// # convert a `syn::Type` rust type to a
+    /// typescript type: basically i32 => number etc.
+    ///
+    /// field is the current Field for which we are trying a conversion
+    pub fn type_to_ts(&self, ty: &syn::Type) -> Type {
+        // `type_to_ts` recursively calls itself occationally
+        // finding a Path which it hands to last_path_element
+        // which generates a "simplified" TSType struct which
+        // is handed to `generic_to_ts` which possibly "bottoms out"
+        // by generating a typescript type.
+
+        use syn::Type::*;
+        use syn::{
+            TypeBareFn, TypeGroup, TypeImplTrait, TypeParamBound, TypeParen, TypePath, TypePtr,
+            TypeReference, TypeSlice, TypeTraitObject, TypeTuple,
+        };
+        match ty {
+            Array(array) => match self.tuple_len(array) {
+                Some(len) => self.fixed_array_type(self.type_to_ts(&array.elem), len),
+                None => self.type_to_array(&array.elem),
+            },
+            Slice(TypeSlice { elem, .. }) => self.type_to_array(elem),
+            Ptr(TypePtr { elem, .. }) => {
+                self.strict_error(ty, "serde can't serialize a raw pointer");
+                self.type_to_array(elem)
+            }
+            Reference(TypeReference { elem, .. }) => self.type_to_ts(elem),
+            // fn(a: A,b: B, c:C) -> D
+            BareFn(TypeBareFn { inputs, .. }) => {
+                self.ctxt
+                    .err_msg(inputs, "we do not support TypeScriptifying functions");
+                Type::named("any")
+            }
+            Never(..) => Type::named("never"),
+            Tuple(TypeTuple { elems, .. }) => {
+                Type::Tuple(elems.iter().map(|t| self.type_to_ts(t)).collect())
+            }
+
+            Path(TypePath { path, .. }) => match last_path_element(path) {
+                Some(ref ts) => self.generic_to_ts(ts),
+                _ => {
+                    self.strict_error(ty, "no typescript type for this (it would be `any`)");
+                    Type::named("any")
+                }
+            },
+            TraitObject(TypeTraitObject { bounds, .. })
+            | ImplTrait(TypeImplTrait { bounds, .. }) => {
+                self.strict_error(ty, "a trait has no typescript type");
+                let elems = bounds
+                    .iter()
+                    .filter_map(|t| match t {
+                        TypeParamBound::Trait(t) => last_path_element(&t.path),
+                        _ => None, // skip lifetime etc.
+                    })
+                    .map(|t| self.generic_to_ts(&t))
+                    .collect();
+
+                // A + B + C => A & B & C
+                Type::intersection(elems)
+            }
+            // the printer adds any parentheses we need
+            Paren(TypeParen { elem, .. }) | Group(TypeGroup { elem, .. }) => self.type_to_ts(elem),
+            // `syn::Type` is `#[non_exhaustive]`
+            _ => {
+                self.strict_error(ty, "no typescript type for this (it would be `any`)");
+                Type::named("any")
+            }
+        }
+    }
+
+    pub fn derive_syn_types(&self, types: &[syn::Type]) -> Vec<Type> {
+        types.iter().map(|ty| self.type_to_ts(ty)).collect()
+    }
+}
@@ -0,0 +1,1056 @@
+// Copyright 2019 Ian Castleden
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The parse pipeline shared by the derive macros and the `typescript-definitions` binary.
+
+use super::attrs::{self, Attrs, EnumStyle, Int64, JsonStyle, MapStyle, OptionStyle, Profile};
+use super::config::Config;
+use super::guard;
+use super::json_schema::Dialect;
+use super::ts::{self, Body, Decl, Expr, Function, Item, Param, Stmt, Type};
+use super::utils::*;
+use super::zod;
+use serde_derive_internals::{ast, attr, Ctxt, Derive};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
+use syn::DeriveInput;
+
+// too many TokenStreams around! give it a different name
+pub type QuoteT = proc_macro2::TokenStream;
+
+/// `[T; N]` arrays up to this length are tuples unless `#[ts(tuple_limit = ...)]` says otherwise
+const TUPLE_LIMIT: usize = 16;
+
+pub(crate) struct QuoteMaker {
+    pub source: Type,
+    /// type guard expression checking `obj`
+    pub guard: Expr,
+    /// zod schema
+    pub zod: Expr,
+    /// enum factory declarations
+    pub enum_factory: Result<Vec<Decl>, &'static str>,
+    /// enum handler declarations
+    pub enum_handler: Result<Vec<Decl>, &'static str>,
+    pub kind: QuoteMakerKind,
+}
+
+pub(crate) enum QuoteMakerKind {
+    Object,
+    /// a typescript `enum` with these members
+    Enum(Vec<ts::EnumMember>),
+    ConstEnum(Vec<ts::EnumMember>),
+    /// a union of string literals, with the `as const` object it is derived from
+    ConstObject(Expr),
+    Union,
+}
+
+pub struct Typescriptify {
+    pub ident: syn::Ident,
+    pub generics: syn::Generics,
+    /// the crate's `typescript-definitions.toml`
+    pub config: Arc<Config>,
+    /// why `typescript-definitions.toml` couldn't be read, reported by `parse()`
+    config_error: Option<String>,
+    /// see `wants_input()`
+    wants_input: bool,
+    input: DeriveInput,
+}
+
+impl Typescriptify {
+    /// parse a derive input using the `typescript-definitions.toml` of the crate being compiled
+    pub fn new(input: QuoteT) -> Self {
+        match Config::load() {
+            Ok(config) => Self::with_config(input, config),
+            Err(err) => Self {
+                config_error: Some(err),
+                ..Self::with_config(input, Arc::default())
+            },
+        }
+    }
+
+    pub fn with_config(input: QuoteT, config: Arc<Config>) -> Self {
+        let input: DeriveInput = syn::parse2(input).unwrap();
+
+        let cx = Ctxt::new();
+
+        let mut attrs = attrs::Attrs::new();
+        attrs.push_doc_comment(&input.attrs);
+        attrs.push_attrs(&input.ident, &input.attrs, Some(&cx));
+
+        let container = ast::Container::from_ast(&cx, &input, Derive::Serialize)
+            .expect("container was derived from AST");
+
+        // `parse()` finds these errors again and reports them at their spans
+        let _ = cx.check();
+
+        let wants_input = attrs.input.or(config.defaults.input).unwrap_or(false);
+
+        Self {
+            generics: container.generics.clone(),
+            ident: container.ident,
+            config,
+            config_error: None,
+            wants_input,
+            input,
+        }
+    }
+
+    /// should `parse_input()` be used: `#[ts(input)]` or `input = true` in the crate's
+    /// `typescript-definitions.toml`. A derive macro doesn't see a `Deserialize` derive
+    pub fn wants_input(&self) -> bool {
+        self.wants_input
+    }
+
+    /// the type serde serializes
+    pub fn parse(&self) -> TSOutput {
+        self.parse_derive(Derive::Serialize)
+    }
+
+    /// the type serde deserializes: `FooInput`
+    pub fn parse_input(&self) -> TSOutput {
+        self.parse_derive(Derive::Deserialize)
+    }
+
+    fn parse_derive(&self, derive: Derive) -> TSOutput {
+        let input = &self.input;
+        let cx = Ctxt::new();
+
+        // collect and check #[ts(...attrs)]
+        let attrs = {
+            let mut attrs = attrs::Attrs::new();
+            attrs.push_doc_comment(&input.attrs);
+            attrs.push_attrs(&input.ident, &input.attrs, Some(&cx));
+            attrs
+        };
+        if let Some(ref err) = self.config_error {
+            cx.error_spanned_by(&input.ident, err);
+        }
+
+        let container =
+            ast::Container::from_ast(&cx, input, derive).expect("container was derived from AST");
+
+        let type_params = type_params(&container, &attrs, &cx);
+        let repr = is_repr(input, &attrs, &cx);
+
+        let (typescript, pctxt) = {
+            let mut pctxt = ParseContext {
+                ctxt: Some(cx),
+                global_attrs: attrs,
+                ident: container.ident.clone(),
+                derive,
+                default: !matches!(container.attrs.default(), attr::Default::None),
+                repr,
+                config: self.config.clone(),
+                type_params,
+                dependencies: RefCell::new(vec![]),
+                input_dependencies: RefCell::new(vec![]),
+                field_attrs: RefCell::new(HashMap::new()),
+            };
+
+            // `T = i32` becomes `T = number`
+            let defaults = container
+                .generics
+                .type_params()
+                .filter(|t| pctxt.is_type_param(t.ident.to_string()))
+                .map(|t| t.default.as_ref().map(|ty| pctxt.type_to_ts(ty)))
+                .collect::<Vec<_>>();
+            for (param, default) in pctxt.type_params.iter_mut().zip(defaults) {
+                param.default = default;
+            }
+
+            let typescript = match container.data {
+                ast::Data::Enum(ref variants) => pctxt.derive_enum(variants, &container),
+                ast::Data::Struct(style, ref fields) => {
+                    pctxt.derive_struct(style, fields, &container)
+                }
+            };
+
+            // erase serde context
+            (typescript, pctxt)
+        };
+
+        TSOutput {
+            ident: pctxt.type_name(&container.ident),
+            pctxt,
+            q_maker: typescript,
+        }
+    }
+}
+
+/// is this enum written as its discriminant: `#[ts(repr)]` with a `#[repr(..)]`.
+/// A derive macro doesn't see the other derives, so a `serde_repr` derive can't tell us
+fn is_repr(input: &DeriveInput, attrs: &Attrs, cx: &Ctxt) -> bool {
+    let has_repr = input.attrs.iter().any(|a| a.path.is_ident("repr"));
+    if attrs.ts_repr && !has_repr {
+        cx.error_spanned_by(&input.ident, "#[ts(repr)] needs a #[repr(..)] attribute");
+    }
+    attrs.ts_repr && has_repr
+}
+
+/// the type parameters of `container` with any `#[ts(bound = "...")]`: all of them, even
+/// one only used by `PhantomData`, so that references to the type (which pass all the
+/// rust arguments) match its arity
+fn type_params(container: &ast::Container, attrs: &Attrs, cx: &Ctxt) -> Vec<TypeParam> {
+    use std::str::FromStr;
+    let mut params = container
+        .generics
+        .type_params()
+        .map(|t| TypeParam {
+            ident: t.ident.clone(),
+            bound: None,
+            default: None,
+        })
+        .collect::<Vec<_>>();
+
+    for (name, bound) in &attrs.ts_bounds {
+        if !container.generics.type_params().any(|t| t.ident == name) {
+            cx.error_spanned_by(
+                &container.ident,
+                format!("bound: \"{}\" is not a type parameter", name),
+            );
+            continue;
+        }
+        match QuoteT::from_str(bound) {
+            Ok(..) => {
+                if let Some(param) = params.iter_mut().find(|t| t.ident == name) {
+                    param.bound = Some(Type::from_source(bound))
+                }
+            }
+            Err(..) => cx.error_spanned_by(
+                &container.ident,
+                format!("bound: can't parse type {}", bound),
+            ),
+        }
+    }
+    params
+}
+
+pub struct TSOutput {
+    pub ident: String,
+    pub(crate) pctxt: ParseContext,
+    pub(crate) q_maker: QuoteMaker,
+}
+
+/// We have multiple kinds of exports that we need to differentiate between when using something like
+/// WASM Bindgen. For WASM-Bindgen, we have types needed for inputs (in the index.d.ts file), but we
+/// also want to provide helper values which cannot exist in the .d.ts file. For this, we have to separate
+/// what are simply type declarations, and what are helper values (functions, etc)
+pub struct TSDefinitions {
+    /// export type A = [number];
+    pub declarations: String,
+    /// export const a: A = [1];
+    pub values: String,
+}
+
+impl TSOutput {
+    /// the errors found while parsing (to report them at their spans), otherwise
+    /// they are a panic when we are dropped
+    pub fn check(&mut self) -> Result<(), Vec<syn::Error>> {
+        match self.pctxt.ctxt.take() {
+            Some(ctxt) => ctxt.check(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn export_type_handler_source(&self) -> Result<String, &'static str> {
+        self.q_maker
+            .enum_handler
+            .as_ref()
+            .map(|decls| self.print_decls(decls))
+            .map_err(|e| *e)
+    }
+
+    pub fn export_type_factory_source(&self) -> Result<String, &'static str> {
+        self.q_maker
+            .enum_factory
+            .as_ref()
+            .map(|decls| self.print_decls(decls))
+            .map_err(|e| *e)
+    }
+
+    /// each declaration on its own line
+    fn print_decls(&self, decls: &[Decl]) -> String {
+        decls.iter().map(|decl| format!("{}\n", decl)).collect()
+    }
+
+    /// the types referenced by this definition, less our own
+    /// name and type parameters
+    pub fn dependencies(&self) -> Vec<String> {
+        let ident = self.pctxt.ident.to_string();
+        self.pctxt
+            .dependencies
+            .borrow()
+            .iter()
+            .filter(|d| **d != ident && *d != "Self" && !self.pctxt.is_type_param(d))
+            .cloned()
+            .collect()
+    }
+
+    /// the types referenced by their `Input` name (`FooInput`), less our own. The
+    /// ones that have no `Input` type of their own need an alias (`Decl::input_alias`)
+    pub fn input_dependencies(&self) -> Vec<String> {
+        let ident = self.pctxt.ident.to_string();
+        self.pctxt
+            .input_dependencies
+            .borrow()
+            .iter()
+            .filter(|d| **d != ident)
+            .cloned()
+            .collect()
+    }
+
+    /// `Foo<T>`
+    fn type_ref(&self) -> Type {
+        Type::Ref(self.ident.clone(), self.pctxt.generics_args())
+    }
+
+    pub fn export_type_guard_source(&self) -> String {
+        let obj = Type::named("any");
+        let mut params = vec![Param::new("obj", obj.clone())];
+        params.extend(self.pctxt.type_params.iter().map(|t| {
+            let is = Type::Predicate("obj".into(), Box::new(Type::named(t.ident.to_string())));
+            Param::new(
+                guard::guard_ident(&t.ident.to_string()),
+                Type::Function(vec![Param::new("obj", obj.clone())], Box::new(is)),
+            )
+        }));
+        Decl::Function {
+            docs: vec![],
+            name: guard::guard_ident(&self.ident),
+            function: Function {
+                type_params: self.pctxt.generics_decl(),
+                params,
+                ret: Some(Type::Predicate("obj".into(), Box::new(self.type_ref()))),
+                body: Body::Block(vec![Stmt::Return(self.q_maker.guard.clone())]),
+            },
+        }
+        .to_string()
+    }
+
+    /// `export const FooSchema: z.ZodType<Foo> = ...`, or for a generic type a function of
+    /// the type parameters' schemas: `<T extends z.ZodTypeAny>(TSchema: T) => ...`
+    pub fn export_type_zod_source(&self) -> String {
+        let name = zod::schema_ident(&self.ident);
+        let decl = if self.pctxt.type_params.is_empty() {
+            Decl::Const {
+                docs: vec![],
+                name,
+                ty: Some(Type::Ref("z.ZodType".into(), vec![self.type_ref()])),
+                value: self.q_maker.zod.clone(),
+            }
+        } else {
+            let names = self
+                .pctxt
+                .type_params
+                .iter()
+                .map(|t| t.ident.to_string())
+                .collect::<Vec<_>>();
+            Decl::Const {
+                docs: vec![],
+                name,
+                ty: None,
+                value: Expr::Arrow(Box::new(Function {
+                    type_params: names
+                        .iter()
+                        .map(|t| ts::TypeParam {
+                            bound: Some(Type::named("z.ZodTypeAny")),
+                            ..ts::TypeParam::new(t.as_str())
+                        })
+                        .collect(),
+                    params: names
+                        .iter()
+                        .map(|t| Param::new(zod::schema_ident(t), Type::named(t.as_str())))
+                        .collect(),
+                    ret: None,
+                    body: Body::Expr(self.q_maker.zod.clone()),
+                })),
+            }
+        };
+        format!("{}\n", decl)
+    }
+
+    /// the JSON Schema of this type, titled with its name and described by its doc comments
+    pub fn export_json_schema_source(&self, dialect: Dialect) -> String {
+        let mut schema = self.pctxt.type_to_schema(&self.q_maker.source, dialect);
+        schema["title"] = self.ident.clone().into();
+        let docs = self.pctxt.global_attrs.docs();
+        if !docs.is_empty() {
+            schema["description"] = docs.join("\n").into();
+        }
+        schema.to_string()
+    }
+
+    /// `export const Foo = <T>(check: Foo<T>) => check`
+    fn check_function(&self) -> Decl {
+        Decl::Const {
+            docs: self.pctxt.global_attrs.docs(),
+            name: self.ident.clone(),
+            ty: None,
+            value: Expr::Arrow(Box::new(Function {
+                type_params: self.pctxt.generics_decl(),
+                params: vec![Param::new("check", self.type_ref())],
+                ret: None,
+                body: Body::Expr(Expr::ident("check")),
+            })),
+        }
+    }
+
+    /// `export type Foo<T extends Bound = Default> = ...`
+    fn type_decl(&self, ty: Type) -> Decl {
+        Decl::Type {
+            docs: self.pctxt.global_attrs.docs(),
+            export: true,
+            name: self.ident.clone(),
+            type_params: self.pctxt.generics_decl(),
+            ty,
+        }
+    }
+
+    fn enum_decl(&self, members: &[ts::EnumMember], is_const: bool) -> Decl {
+        Decl::Enum {
+            docs: self.pctxt.global_attrs.docs(),
+            is_const,
+            name: self.ident.clone(),
+            members: members.to_vec(),
+        }
+    }
+
+    /// the output of `type_script_ify()`: declarations followed by values
+    pub fn type_script_ify_source(&self) -> String {
+        ts::print(&self.type_script_ify_ast(), &ts::TsFormat::default())
+    }
+
+    /// the syntax tree of `type_script_ify_source()`, which the derive registers
+    /// (serialized) so it can be printed again with another `TsFormat`
+    pub fn type_script_ify_ast(&self) -> Vec<Item> {
+        let decls = |decls: &[Decl]| {
+            decls
+                .iter()
+                .flat_map(|decl| vec![Item::Decl(decl.clone()), Item::Newline])
+                .collect::<Vec<_>>()
+        };
+        let mut items = vec![
+            Item::Decl(self.export_type_declaration_ast()),
+            Item::Newline,
+        ];
+        match self.q_maker.kind {
+            // an enum is a value as well as a type: it is declared once
+            QuoteMakerKind::Enum(..) | QuoteMakerKind::ConstEnum(..) => {}
+            QuoteMakerKind::ConstObject(ref object) => {
+                items[0] = Item::Decl(self.const_object_decl());
+                items.extend(decls(&[Decl::Const {
+                    docs: self.pctxt.global_attrs.docs(),
+                    name: self.ident.clone(),
+                    ty: None,
+                    value: Expr::AsConst(Box::new(object.clone())),
+                }]));
+            }
+            QuoteMakerKind::Union => {
+                // factories and handlers need a serde tag to switch on
+                if let (Ok(factory), Ok(handler)) =
+                    (&self.q_maker.enum_factory, &self.q_maker.enum_handler)
+                {
+                    items.extend(decls(factory));
+                    items.push(Item::Newline);
+                    items.extend(decls(handler));
+                }
+            }
+            QuoteMakerKind::Object => items.extend(decls(&[self.check_function()])),
+        }
+        items
+    }
+
+    /// `type_script_ify_ast()` as JSON
+    pub fn type_script_ify_json(&self) -> String {
+        serde_json::to_string(&self.type_script_ify_ast()).expect("syntax tree serializes")
+    }
+
+    /// just the declaration, without any values (factories etc.)
+    pub fn export_type_declaration_source(&self) -> String {
+        self.export_type_declaration_ast().to_string()
+    }
+
+    /// the syntax tree of `export_type_declaration_source()`
+    pub fn export_type_declaration_ast(&self) -> Decl {
+        match self.q_maker.kind {
+            QuoteMakerKind::Enum(ref members) => self.enum_decl(members, false),
+            QuoteMakerKind::ConstEnum(ref members) => self.enum_decl(members, true),
+            // without the object, this is just the union
+            QuoteMakerKind::Union | QuoteMakerKind::Object | QuoteMakerKind::ConstObject(..) => {
+                self.type_decl(self.q_maker.source.clone())
+            }
+        }
+    }
+
+    /// `export_type_declaration_ast()` (an item list of one) as JSON
+    pub fn export_type_declaration_json(&self) -> String {
+        serde_json::to_string(&[Item::Decl(self.export_type_declaration_ast())])
+            .expect("syntax tree serializes")
+    }
+
+    /// `export type Foo = (typeof Foo)[keyof typeof Foo]`
+    fn const_object_decl(&self) -> Decl {
+        let typeof_ident = Type::Typeof(self.ident.clone());
+        self.type_decl(Type::IndexedAccess(
+            Box::new(typeof_ident.clone()),
+            Box::new(Type::Keyof(Box::new(typeof_ident))),
+        ))
+    }
+
+    pub fn export_type_definition_source(&self) -> TSDefinitions {
+        match self.q_maker.kind {
+            QuoteMakerKind::Enum(..) | QuoteMakerKind::ConstEnum(..) => {
+                let declarations = self.export_type_declaration_source();
+                TSDefinitions {
+                    values: declarations.clone(),
+                    declarations,
+                }
+            }
+            QuoteMakerKind::ConstObject(ref object) => TSDefinitions {
+                declarations: self.const_object_decl().to_string(),
+                values: format!(
+                    "{}\n",
+                    Decl::Const {
+                        docs: self.pctxt.global_attrs.docs(),
+                        name: self.ident.clone(),
+                        ty: None,
+                        value: Expr::AsConst(Box::new(object.clone())),
+                    }
+                ),
+            },
+            QuoteMakerKind::Union => TSDefinitions {
+                declarations: self.export_type_declaration_source(),
+                values: match (
+                    self.export_type_factory_source(),
+                    self.export_type_handler_source(),
+                ) {
+                    (Ok(factory), Ok(handler)) => format!("{}\n{}", factory, handler),
+                    _ => String::new(),
+                },
+            },
+            QuoteMakerKind::Object => TSDefinitions {
+                declarations: self.export_type_declaration_source(),
+                // check create function
+                values: format!("{}\n", self.check_function()),
+            },
+        }
+    }
+}
+
+fn return_type(rt: &syn::ReturnType) -> Option<syn::Type> {
+    match rt {
+        syn::ReturnType::Default => None, // e.g. undefined
+        syn::ReturnType::Type(_, tp) => Some(*tp.clone()),
+    }
+}
+
+// represents a typescript type T<A,B>
+pub(crate) struct TSType {
+    pub ident: syn::Ident,
+    pub args: Vec<syn::Type>,
+    pub path: Vec<syn::Ident>,          // full path
+    pub return_type: Option<syn::Type>, // only if function
+}
+
+impl TSType {
+    pub fn path(&self) -> Vec<String> {
+        self.path.iter().map(|i| i.to_string()).collect() // hold the memory
+    }
+}
+
+pub(crate) fn last_path_element(path: &syn::Path) -> Option<TSType> {
+    let fullpath = path
+        .segments
+        .iter()
+        .map(|s| s.ident.clone())
+        .collect::<Vec<_>>();
+    match path.segments.last() {
+        Some(t) => {
+            let ident = t.ident.clone();
+            let args = match &t.arguments {
+                syn::PathArguments::AngleBracketed(ref path) => &path.args,
+                // closures Fn(A,B) -> C
+                syn::PathArguments::Parenthesized(ref path) => {
+                    let args: Vec<_> = path.inputs.iter().cloned().collect();
+                    let ret = return_type(&path.output);
+                    return Some(TSType {
+                        ident,
+                        args,
+                        path: fullpath,
+                        return_type: ret,
+                    });
+                }
+                syn::PathArguments::None => {
+                    return Some(TSType {
+                        ident,
+                        args: vec![],
+                        path: fullpath,
+                        return_type: None,
+                    });
+                }
+            };
+            // ignore lifetimes
+            let args = args
+                .iter()
+                .filter_map(|p| match p {
+                    syn::GenericArgument::Type(t) => Some(t),
+                    syn::GenericArgument::Binding(t) => Some(&t.ty),
+                    syn::GenericArgument::Constraint(..) => None,
+                    syn::GenericArgument::Const(..) => None,
+                    _ => None, // lifetimes, expr, constraints A : B ... skip!
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+
+            Some(TSType {
+                ident,
+                path: fullpath,
+                args,
+                return_type: None,
+            })
+        }
+        None => None,
+    }
+}
+
+pub(crate) struct FieldContext<'a> {
+    pub ctxt: &'a ParseContext,            // global parse context
+    pub field: Option<&'a ast::Field<'a>>, // field being parsed
+    pub attrs: Rc<Attrs>,                  // field attributes
+}
+
+impl<'a> FieldContext<'a> {
+    pub fn int64(&self) -> Int64 {
+        self.attrs.int64.unwrap_or_else(|| self.ctxt.int64())
+    }
+
+    /// a `#[serde(with = "serde_bytes")]` field written as a `Uint8Array`
+    pub fn is_byte_array(&self) -> bool {
+        self.ctxt.binary_bytes() && matches!(self.field, Some(f) if is_serde_bytes(f))
+    }
+
+    /// `#[ts(map_style = "...")]` on the field, else on the container
+    pub fn map_style(&self) -> MapStyle {
+        self.attrs
+            .map_style
+            .unwrap_or_else(|| self.ctxt.map_style())
+    }
+
+    /// `#[ts(readonly)]` on the field, else on the container
+    pub fn readonly(&self) -> bool {
+        self.attrs.readonly.unwrap_or_else(|| self.ctxt.readonly())
+    }
+
+    /// `Some(N)` if the `[T; N]` array is written as a tuple: `N` is a literal
+    /// no longer than the `tuple_limit` (and the field isn't `as_byte_string` or `serde_bytes`)
+    pub fn tuple_len(&self, array: &syn::TypeArray) -> Option<usize> {
+        if matches!(self.field, Some(f) if is_bytes(f) || is_serde_bytes(f)) {
+            return None;
+        }
+        let limit = self
+            .attrs
+            .tuple_limit
+            .unwrap_or_else(|| self.ctxt.tuple_limit());
+        array_len(&array.len).filter(|&n| n <= limit)
+    }
+
+    pub fn get_path(&self, ty: &syn::Type) -> Option<TSType> {
+        use syn::Type::Path;
+        use syn::TypePath;
+        match ty {
+            Path(TypePath { path, .. }) => last_path_element(path),
+            _ => None,
+        }
+    }
+}
+
+/// a typescript type parameter `T extends Bound = Default`
+pub(crate) struct TypeParam {
+    pub ident: syn::Ident,
+    /// from `#[ts(bound = "T: Bound")]`
+    pub bound: Option<Type>,
+    pub default: Option<Type>,
+}
+
+impl TypeParam {
+    pub fn to_decl(&self) -> ts::TypeParam {
+        ts::TypeParam {
+            name: self.ident.to_string(),
+            bound: self.bound.clone(),
+            default: self.default.clone(),
+        }
+    }
+}
+
+pub(crate) struct ParseContext {
+    pub ctxt: Option<Ctxt>,          // serde parse context for error reporting
+    pub global_attrs: Attrs,         // global #[ts(...)] attributes
+    pub ident: syn::Ident,           // name of enum struct
+    pub derive: Derive,              // serializing or deserializing (an `Input` type)
+    pub default: bool,               // container has #[serde(default)]
+    pub repr: bool,                  // enum is serialized as its discriminant
+    pub config: Arc<Config>,         // typescript-definitions.toml
+    pub type_params: Vec<TypeParam>, // generic type parameters
+    pub dependencies: RefCell<Vec<String>>, // names of the (non builtin) types referenced
+    /// the types we reference by their `Input` name
+    pub input_dependencies: RefCell<Vec<String>>,
+    /// the `#[ts(...)]` attributes of each field (by its address in the input), so
+    /// they are parsed and any errors reported once
+    pub field_attrs: RefCell<HashMap<*const syn::Field, Rc<Attrs>>>,
+}
+
+impl Drop for ParseContext {
+    fn drop(&mut self) {
+        if !std::thread::panicking() {
+            // must track this in case of errors so we can check them
+            // if we don't consume the errors, we'll get an "unhandled errors" panic whether or not there were errors
+            if let Some(ctxt) = self.ctxt.take() {
+                ctxt.check().expect("no errors")
+            }
+        }
+    }
+}
+
+impl<'a> ParseContext {
+    // Some helpers
+
+    /// record a referenced type name, keeping first-seen order
+    pub fn add_dependency(&self, ident: &syn::Ident) {
+        let name = ident.to_string();
+        let mut deps = self.dependencies.borrow_mut();
+        if !deps.contains(&name) {
+            deps.push(name);
+        }
+    }
+
+    /// are we describing what serde deserializes (an `Input` type)?
+    pub fn is_input(&self) -> bool {
+        matches!(self.derive, Derive::Deserialize)
+    }
+
+    pub fn is_type_param<T: AsRef<str>>(&self, name: T) -> bool {
+        self.type_params.iter().any(|t| t.ident == name.as_ref())
+    }
+
+    /// `<T extends Bound = Default, U>`
+    pub fn generics_decl(&self) -> Vec<ts::TypeParam> {
+        self.type_params.iter().map(TypeParam::to_decl).collect()
+    }
+
+    /// `<T, U>`
+    pub fn generics_args(&self) -> Vec<Type> {
+        self.type_params
+            .iter()
+            .map(|t| Type::named(t.ident.to_string()))
+            .collect()
+    }
+
+    /// convert a rust type that doesn't belong to a field (e.g. a default type parameter)
+    pub fn type_to_ts(&self, ty: &syn::Type) -> Type {
+        let fc = FieldContext {
+            attrs: Rc::new(Attrs::new()),
+            ctxt: self,
+            field: None,
+        };
+        fc.type_to_ts(ty)
+    }
+
+    /// the typescript name of a type we reference: `Foo` or `FooInput`
+    pub fn type_name(&self, ident: &syn::Ident) -> String {
+        let name = ident.to_string();
+        if self.is_input() && !self.is_type_param(&name) {
+            format!("{}Input", name)
+        } else {
+            name
+        }
+    }
+
+    /// the name we reference another type by: `Foo`, or `FooInput` (recorded in
+    /// `input_dependencies`) when we are an `Input` type
+    pub fn reference_name(&self, ident: &syn::Ident) -> String {
+        let name = self.type_name(ident);
+        let mut inputs = self.input_dependencies.borrow_mut();
+        if *ident != name && !inputs.iter().any(|i| ident == i) {
+            inputs.push(ident.to_string());
+        }
+        name
+    }
+
+    /// is `name` (`Foo` or `FooInput`) a type we reference, which has a guard and
+    /// schema of its own, rather than one from a `ts_type` or `[types]` mapping?
+    pub fn is_reference(&self, name: &str) -> bool {
+        self.dependencies.borrow().iter().any(|d| d == name)
+            || name
+                .strip_suffix("Input")
+                .is_some_and(|name| self.input_dependencies.borrow().iter().any(|d| d == name))
+    }
+
+    /// the serde name (of a field or variant) for our direction
+    pub fn serde_name(&self, name: &attr::Name) -> String {
+        if self.is_input() {
+            name.deserialize_name()
+        } else {
+            name.serialize_name()
+        }
+    }
+
+    pub fn is_skipped_field(&self, field: &ast::Field) -> bool {
+        if self.is_input() {
+            field.attrs.skip_deserializing()
+        } else {
+            field.attrs.skip_serializing()
+        }
+    }
+
+    pub fn is_skipped_variant(&self, variant: &ast::Variant) -> bool {
+        if self.is_input() {
+            variant.attrs.skip_deserializing()
+        } else {
+            variant.attrs.skip_serializing()
+        }
+    }
+
+    /// remove skipped and `PhantomData` fields
+    pub fn filter_visible(&self, fields: &'a [ast::Field<'a>]) -> Vec<&'a ast::Field<'a>> {
+        fields
+            .iter()
+            .filter(|field| !self.is_skipped_field(field) && !is_phantom(field.ty))
+            .collect()
+    }
+
+    /// `#[ts(enum_style = "...")]`, else the crate's `typescript-definitions.toml`
+    pub fn enum_style(&self) -> EnumStyle {
+        self.global_attrs
+            .enum_style
+            .or(self.config.defaults.enum_style)
+            .unwrap_or_default()
+    }
+
+    /// `#[ts(json_style = "...")]`, else the crate's `typescript-definitions.toml`
+    pub fn json_style(&self) -> JsonStyle {
+        self.global_attrs
+            .json_style
+            .or(self.config.defaults.json_style)
+            .unwrap_or_default()
+    }
+
+    /// `strict = true` in the crate's `typescript-definitions.toml` makes `#[ts(strict)]` the default
+    pub fn strict(&self) -> bool {
+        self.global_attrs
+            .strict
+            .or(self.config.defaults.strict)
+            .unwrap_or(false)
+    }
+
+    /// longer `[T; N]` arrays are written as `T[]`
+    pub fn tuple_limit(&self) -> usize {
+        self.global_attrs.tuple_limit.unwrap_or(TUPLE_LIMIT)
+    }
+
+    /// `readonly = true` in the crate's `typescript-definitions.toml` makes `#[ts(readonly)]` the default
+    pub fn readonly(&self) -> bool {
+        self.global_attrs
+            .readonly
+            .or(self.config.defaults.readonly)
+            .unwrap_or(false)
+    }
+
+    /// `#[ts(profile = "...")]`, else the crate's `typescript-definitions.toml`
+    pub fn profile(&self) -> Profile {
+        self.global_attrs
+            .profile
+            .or(self.config.defaults.profile)
+            .unwrap_or_default()
+    }
+
+    /// bytes (`#[serde(with = "serde_bytes")]`, `bytes::Bytes`) are a `Uint8Array`
+    /// in the binary formats
+    pub fn binary_bytes(&self) -> bool {
+        self.profile() != Profile::Json
+    }
+
+    /// serde-wasm-bindgen writes `bigint`s, otherwise the crate's `typescript-definitions.toml` decides
+    pub fn int64(&self) -> Int64 {
+        match self.profile() {
+            Profile::WasmBindgen => Int64::Bigint,
+            _ => self.config.defaults.int64.unwrap_or_default(),
+        }
+    }
+
+    pub fn option_style(&self) -> OptionStyle {
+        let default = match self.profile() {
+            Profile::WasmBindgen => OptionStyle::Undefined,
+            _ => OptionStyle::Null,
+        };
+        self.global_attrs
+            .option_style
+            .or(self.config.defaults.option_style)
+            .unwrap_or(default)
+    }
+
+    pub fn map_style(&self) -> MapStyle {
+        let default = match self.profile() {
+            Profile::WasmBindgen => MapStyle::EsMap,
+            _ => MapStyle::Record,
+        };
+        self.global_attrs
+            .map_style
+            .or(self.config.defaults.map_style)
+            .unwrap_or(default)
+    }
+
+    /// `Some(unwrap)` if the key of `field` may be missing: `a?: T`. `unwrap` is
+    /// true if an `Option` field is then never `null` (so `T` instead of `T | null`).
+    pub fn optional_field(&self, field: &ast::Field, attrs: &Attrs) -> Option<bool> {
+        let is_option = attrs.ts_type.is_none()
+            && option_type(attrs.ts_as.as_ref().unwrap_or(field.ty)).is_some();
+        if attrs.ts_optional || (is_option && self.option_style() == OptionStyle::Optional) {
+            return Some(is_option);
+        }
+        if self.is_input() {
+            // serde fills in missing `#[serde(default)]` fields and `Option`s
+            let default = self.default || !matches!(field.attrs.default(), attr::Default::None);
+            if default || (is_option && field.attrs.deserialize_with().is_none()) {
+                return Some(false);
+            }
+        } else if let Some(path) = field.attrs.skip_serializing_if() {
+            // `skip_serializing_if = "Option::is_none"` never writes a `null`
+            let is_none = matches!(path.path.segments.last(), Some(s) if s.ident == "is_none");
+            return Some(is_option && is_none);
+        }
+        None
+    }
+
+    /// the `#[ts(...)]` attributes of `field`
+    pub fn field_attrs(&self, field: &ast::Field) -> Rc<Attrs> {
+        self.field_attrs
+            .borrow_mut()
+            .entry(field.original as *const syn::Field)
+            .or_insert_with(|| Rc::new(Attrs::from_field(field, self.ctxt.as_ref())))
+            .clone()
+    }
+
+    pub fn err_msg<A: quote::ToTokens>(&self, tokens: A, msg: &str) {
+        if let Some(ref ctxt) = self.ctxt {
+            ctxt.error_spanned_by(tokens, msg);
+        } else {
+            panic!("{}", msg.to_string())
+        }
+    }
+
+    /// returns the type of `field`
+    pub fn field_to_ts(&self, field: &ast::Field<'a>) -> Type {
+        let attrs = self.field_attrs(field);
+        // if user has provided a type ... use that
+        if let Some(ref s) = attrs.ts_type {
+            use std::str::FromStr;
+            if QuoteT::from_str(s).is_err() {
+                self.err_msg(
+                    field.original,
+                    &format!("{}: can't parse type {}", self.ident, s),
+                );
+            }
+            return Type::from_source(s);
+        }
+
+        let fc = FieldContext {
+            attrs,
+            ctxt: self,
+            field: Some(field),
+        };
+        if let Some(ref ty) = fc.attrs.ts_as {
+            fc.type_to_ts(ty)
+        } else {
+            fc.type_to_ts(field.ty)
+        }
+    }
+
+    /// returns `field_name: ty`
+    pub fn derive_field(&self, field: &ast::Field<'a>) -> ts::Member {
+        let key = self.serde_name(field.attrs.name()); // use serde name instead of field.member
+        let attrs = self.field_attrs(field);
+        let docs = attrs.docs();
+        let (optional, ty) = match self.optional_field(field, &attrs) {
+            Some(true) => (
+                true,
+                self.field_option_to_ts(field).expect("field is an Option"),
+            ),
+            Some(false) => (true, self.field_to_ts(field)),
+            None => (false, self.field_to_ts(field)),
+        };
+        ts::Member::Property {
+            docs,
+            readonly: attrs.readonly.unwrap_or_else(|| self.readonly()),
+            key,
+            optional,
+            ty,
+        }
+    }
+
+    /// returns `field_name: ty` for all but the `#[serde(flatten)]` fields
+    pub fn derive_fields(&self, fields: &[&ast::Field<'a>]) -> Vec<ts::Member> {
+        fields
+            .iter()
+            .filter(|f| !f.attrs.flatten())
+            .map(|f| self.derive_field(f))
+            .collect()
+    }
+
+    /// returns `T` for an `Option<T>` field
+    pub fn field_option_to_ts(&self, field: &ast::Field<'a>) -> Option<Type> {
+        let attrs = self.field_attrs(field);
+        if attrs.ts_type.is_some() {
+            return None;
+        }
+        let inner = option_type(attrs.ts_as.as_ref().unwrap_or(field.ty))?;
+        let fc = FieldContext {
+            attrs,
+            ctxt: self,
+            field: Some(field),
+        };
+        Some(fc.type_to_ts(&inner))
+    }
+
+    /// returns the type of a `#[serde(flatten)]` field.
+    pub fn derive_flatten_field(&self, field: &ast::Field<'a>) -> Type {
+        // a flattened `None` adds no keys at all
+        match self.field_option_to_ts(field) {
+            Some(inner) => Type::Ref("Partial".into(), vec![inner]),
+            None => self.field_to_ts(field),
+        }
+    }
+
+    /// returns the types of the `#[serde(flatten)]` fields. These
+    /// are intersected with the object of the other fields `{ a: number } & Pagination`
+    pub fn derive_flatten(&self, fields: &[&ast::Field<'a>]) -> Vec<Type> {
+        fields
+            .iter()
+            .filter(|f| f.attrs.flatten())
+            .map(|f| self.derive_flatten_field(f))
+            .collect()
+    }
+
+    /// returns `{ field_name: ty; ... } & flattened`
+    pub fn derive_object(&self, fields: &[&ast::Field<'a>]) -> Type {
+        let content = self.derive_fields(fields);
+        let flatten = self.derive_flatten(fields);
+        if content.is_empty() && !flatten.is_empty() {
+            Type::intersection(flatten)
+        } else {
+            let mut v = vec![Type::Object(content)];
+            v.extend(flatten);
+            Type::intersection(v)
+        }
+    }
+
+    pub fn derive_field_tuple(&self, fields: &[&ast::Field<'a>]) -> Vec<Type> {
+        fields.iter().map(|f| self.field_to_ts(f)).collect()
+    }
+}
@@ -0,0 +1,246 @@
+// Copyright 2019 Ian Castleden
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+use super::ts::{Decl, Item, Type};
+use super::{ast, TSType};
+use proc_macro2::{Ident, Span};
+
+pub fn ident_from_str(s: &str) -> Ident {
+    syn::Ident::new(s, Span::call_site())
+}
+
+pub fn field_type_name(ty: &syn::Type) -> Option<String> {
+    use syn::Type::Path;
+    match ty {
+        Path(syn::TypePath { path, .. }) => path.segments.last().map(|t| t.ident.to_string()),
+        _ => None,
+    }
+}
+
+pub fn is_bytes(field: &ast::Field<'_>) -> bool {
+    // check for #[serde(with="serde_bytes")]
+    use syn::ExprPath;
+    if let Some(ExprPath { ref path, .. }) = field.attrs.serialize_with() {
+        match path.segments.last() {
+            Some(t) => return t.ident == "as_byte_string",
+            _ => return false,
+        }
+    };
+    false
+}
+
+/// `#[serde(with = "serde_bytes")]`: written with `serialize_bytes`
+pub fn is_serde_bytes(field: &ast::Field<'_>) -> bool {
+    match field.attrs.serialize_with() {
+        Some(syn::ExprPath { path, .. }) => path.segments.iter().any(|s| s.ident == "serde_bytes"),
+        None => false,
+    }
+}
+
+/// `serde_bytes::ByteBuf` and (with the `bytes-impl` feature) `bytes::Bytes` are
+/// written with `serialize_bytes`
+pub fn is_byte_buffer(ts: &TSType) -> bool {
+    let name = ts.ident.to_string();
+    name == "ByteBuf"
+        || (cfg!(feature = "bytes-impl") && matches!(name.as_ref(), "Bytes" | "BytesMut"))
+}
+
+#[allow(unused)]
+pub fn full_field_type_name(ty: &syn::Type) -> Option<Vec<Ident>> {
+    use syn::Type::Path;
+    match ty {
+        Path(syn::TypePath { path, .. }) => {
+            Some(path.segments.iter().map(|p| p.ident.clone()).collect())
+        }
+        _ => None,
+    }
+}
+
+/// returns `T` if `ty` is `Option<T>`
+pub fn option_type(ty: &syn::Type) -> Option<syn::Type> {
+    use syn::Type::Path;
+    match ty {
+        Path(syn::TypePath { path, .. }) => match path.segments.last() {
+            Some(syn::PathSegment {
+                ident,
+                arguments: syn::PathArguments::AngleBracketed(args),
+            }) if ident == "Option" && args.args.len() == 1 => match args.args[0] {
+                syn::GenericArgument::Type(ref t) => Some(t.clone()),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// the length `N` of a `[T; N]` array when it is an integer literal
+pub fn array_len(len: &syn::Expr) -> Option<usize> {
+    use syn::{Expr, ExprLit, ExprParen, Lit};
+    match len {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(n), ..
+        }) => n.base10_parse().ok(),
+        Expr::Paren(ExprParen { expr, .. }) => array_len(expr),
+        _ => None, // e.g. a const generic `N`
+    }
+}
+
+/// integers that don't fit in a javascript `number`
+pub fn is_int64(name: &str) -> bool {
+    matches!(name, "u64" | "i64" | "u128" | "i128" | "usize" | "isize")
+}
+
+pub fn is_phantom(ty: &syn::Type) -> bool {
+    match field_type_name(ty) {
+        Some(t) => t == "PhantomData",
+        _ => false,
+    }
+}
+
+/// With the `*-impl` features: the std type that a type from a popular crate
+/// serializes like (e.g. `IndexMap<K, V>` as `HashMap<K, V>`).
+pub fn ecosystem_type(ts: &TSType) -> Option<syn::Type> {
+    let name = ts.ident.to_string();
+    let path = ts.path();
+    let arg = |n: usize| ts.args.get(n);
+    let ty: syn::Type = match name.as_ref() {
+        "Uuid" if cfg!(feature = "uuid-impl") => syn::parse_quote!(String),
+        "DateTime" | "NaiveDate" | "NaiveDateTime" | "NaiveTime"
+            if cfg!(feature = "chrono-impl") =>
+        {
+            syn::parse_quote!(String)
+        }
+        // as (seconds, nanoseconds); a bare `Duration` is `std::time::Duration`
+        "TimeDelta" if cfg!(feature = "chrono-impl") => syn::parse_quote!((i64, i32)),
+        "Duration" if cfg!(feature = "chrono-impl") && path.len() > 1 && path[0] == "chrono" => {
+            syn::parse_quote!((i64, i32))
+        }
+        // time's serde impls write tuples unless its `serde-human-readable` feature is on
+        // (map the type to "string" in `[types]` for that, or for `time::serde::rfc3339`)
+        "OffsetDateTime" if cfg!(feature = "time-impl") => {
+            // year, ordinal day, hour, minute, second, nanosecond and the offset's hours, minutes, seconds
+            syn::parse_quote!((i32, u16, u8, u8, u8, u32, i8, i8, i8))
+        }
+        "PrimitiveDateTime" if cfg!(feature = "time-impl") => {
+            syn::parse_quote!((i32, u16, u8, u8, u8, u32))
+        }
+        "UtcOffset" if cfg!(feature = "time-impl") => syn::parse_quote!((i8, i8, i8)),
+        // `Date`, `Time` and `Duration` are too common to match without the `time::`
+        "Date" if cfg!(feature = "time-impl") && path.len() > 1 && path[0] == "time" => {
+            syn::parse_quote!((i32, u16))
+        }
+        "Time" if cfg!(feature = "time-impl") && path.len() > 1 && path[0] == "time" => {
+            syn::parse_quote!((u8, u8, u8, u32))
+        }
+        "Duration" if cfg!(feature = "time-impl") && path.len() > 1 && path[0] == "time" => {
+            // seconds, nanoseconds
+            syn::parse_quote!((i64, i32))
+        }
+        "Url" if cfg!(feature = "url-impl") => syn::parse_quote!(String),
+        "Decimal" if cfg!(feature = "rust-decimal-impl") => syn::parse_quote!(String),
+        "IndexMap" if cfg!(feature = "indexmap-impl") && ts.args.len() >= 2 => {
+            let (k, v) = (&ts.args[0], &ts.args[1]);
+            syn::parse_quote!(HashMap<#k, #v>)
+        }
+        "IndexSet" if cfg!(feature = "indexmap-impl") && !ts.args.is_empty() => {
+            let k = &ts.args[0];
+            syn::parse_quote!(HashSet<#k>)
+        }
+        // SmallVec<[T; N]>
+        "SmallVec" if cfg!(feature = "smallvec-impl") => match arg(0) {
+            Some(syn::Type::Array(a)) => {
+                let elem = &a.elem;
+                syn::parse_quote!(Vec<#elem>)
+            }
+            _ => return None,
+        },
+        // ArrayVec<T, N> (or ArrayVec<[T; N]> before 0.6)
+        "ArrayVec" if cfg!(feature = "arrayvec-impl") => match arg(0) {
+            Some(syn::Type::Array(a)) => {
+                let elem = &a.elem;
+                syn::parse_quote!(Vec<#elem>)
+            }
+            Some(elem) => syn::parse_quote!(Vec<#elem>),
+            None => return None,
+        },
+        "ArrayString" if cfg!(feature = "arrayvec-impl") => syn::parse_quote!(String),
+        "Bytes" | "BytesMut" if cfg!(feature = "bytes-impl") => syn::parse_quote!(Vec<u8>),
+        "OrderedFloat" | "NotNan" if cfg!(feature = "ordered-float-impl") => match arg(0) {
+            Some(t) => t.clone(),
+            None => return None,
+        },
+        _ => return None,
+    };
+    Some(ty)
+}
+
+/// the typescript for `serde_json::Value` (with the default `json_style`)
+pub const JSON_VALUE: &str = "JsonValue";
+pub const JSON_VALUE_DEFINITION: &str = "export type JsonValue = null | boolean | number | string | JsonValue[] | { [key: string]: JsonValue }";
+
+/// the syntax tree of `JSON_VALUE_DEFINITION` (as JSON) for its registration. The type
+/// is raw: as a union with an object member it would be printed one variant per line
+pub fn json_value_definition_json() -> String {
+    let decl = Decl::Type {
+        docs: vec![],
+        export: true,
+        name: JSON_VALUE.into(),
+        type_params: vec![],
+        ty: Type::Raw(
+            "null | boolean | number | string | JsonValue[] | { [key: string]: JsonValue }".into(),
+        ),
+    };
+    serde_json::to_string(&[Item::Decl(decl)]).expect("syntax tree serializes")
+}
+
+/// `serde_json::Value` or `serde_json::value::RawValue` written out in full. A bare `Value`
+/// is only `JsonValue` when the `[types]` of `typescript-definitions.toml` say so.
+pub fn is_json_value(ts: &TSType) -> bool {
+    let path = ts.path();
+    let path = path.iter().map(String::as_str).collect::<Vec<_>>();
+    ts.args.is_empty()
+        && matches!(
+            path[..],
+            ["serde_json", "Value"]
+                | ["serde_json", "value", "Value"]
+                | ["serde_json", "value", "RawValue"]
+        )
+}
+
+/// `serde_json::Map<String, Value>` written out in full
+pub fn is_json_map(ts: &TSType) -> bool {
+    let path = ts.path();
+    let path = path.iter().map(String::as_str).collect::<Vec<_>>();
+    matches!(
+        path[..],
+        ["serde_json", "Map"] | ["serde_json", "map", "Map"] | ["serde_json", "value", "Map"]
+    )
+}
+
+/// a type serde_json can't use as a map key (it only takes strings, numbers,
+/// bools and unit variants)
+pub fn is_composite(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Tuple(t) => !t.elems.is_empty(),
+        syn::Type::Array(..) | syn::Type::Slice(..) => true,
+        syn::Type::Reference(r) => is_composite(&r.elem),
+        _ => matches!(
+            field_type_name(ty).as_deref(),
+            Some(
+                "Vec"
+                    | "VecDeque"
+                    | "LinkedList"
+                    | "HashMap"
+                    | "BTreeMap"
+                    | "HashSet"
+                    | "BTreeSet"
+                    | "Option"
+            )
+        ),
+    }
+}
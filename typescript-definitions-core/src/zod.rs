@@ -0,0 +1,229 @@
+// Copyright 2019 Ian Castleden
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! # Zod
+//!
+//! [zod](https://zod.dev) schemas for the types we generate, e.g.
+//! `export const PointSchema: z.ZodType<Point> = z.object({ x: z.number(), y: z.number() })`.
+//!
+//! Other types are referenced as `z.lazy(() => FooSchema)` so the schemas can be
+//! declared in any order (and be recursive). Names that aren't types we reference
+//! (from a `ts_type` or a `[types]` mapping, e.g. `UUID`) have no schema: they are
+//! trusted as `z.custom<UUID>()`.
+
+use super::ts::{Expr, Member, Prop, Type};
+use super::{ParseContext, QuoteMakerKind, JSON_VALUE};
+
+/// `FooSchema`: the name of the schema of `Foo` (or of the type parameter `T`)
+pub fn schema_ident(name: &str) -> String {
+    format!("{}Schema", name)
+}
+
+/// `z.name(args)`
+fn z(name: &str, args: Vec<Expr>) -> Expr {
+    Expr::ident("z").method(name, args)
+}
+
+/// `z.union([a, b])`, or just `a`
+fn union(mut options: Vec<Expr>) -> Expr {
+    match options.len() {
+        0 => z("never", vec![]),
+        1 => options.remove(0),
+        _ => z("union", vec![Expr::Array(options)]),
+    }
+}
+
+/// `z.custom<T>()`: any value, typed as `ty`
+fn custom(ty: &Type) -> Expr {
+    Expr::TypeArgs(
+        Box::new(Expr::ident("z").member("custom")),
+        vec![ty.clone()],
+    )
+    .call(vec![])
+}
+
+/// `z.object({ key: schema })`, keeping the members' doc comments
+fn object(members: &[Member], to_zod: impl Fn(&Type) -> Expr) -> Expr {
+    let mut props = vec![];
+    let mut index = None;
+    for member in members {
+        match member {
+            Member::Property {
+                docs,
+                key,
+                optional,
+                ty,
+                ..
+            } => {
+                let value = to_zod(ty);
+                props.push(Prop::Value {
+                    docs: docs.clone(),
+                    key: key.clone(),
+                    value: if *optional {
+                        value.method("optional", vec![])
+                    } else {
+                        value
+                    },
+                });
+            }
+            Member::Index { key, value } => index = Some((key, value)),
+            // methods and call signatures aren't data
+            _ => {}
+        }
+    }
+    match index {
+        // `{ [key: K]: V }`
+        Some((key, value)) if props.is_empty() => {
+            z("record", vec![record_key(key, &to_zod), to_zod(value)])
+        }
+        // `{ a: A; [key: string]: V }`
+        Some((_, value)) => {
+            z("object", vec![Expr::Object(props)]).method("catchall", vec![to_zod(value)])
+        }
+        None => z("object", vec![Expr::Object(props)]),
+    }
+}
+
+/// the keys of a javascript object are strings, so a `number` key is coerced
+fn record_key(key: &Type, to_zod: impl Fn(&Type) -> Expr) -> Expr {
+    if *key == Type::named("number") {
+        Expr::ident("z").member("coerce").method("number", vec![])
+    } else {
+        to_zod(key)
+    }
+}
+
+impl ParseContext {
+    /// the zod schema checking a value is of type `ty`
+    pub(crate) fn type_to_zod(&self, ty: &Type) -> Expr {
+        let to_zod = |ty: &Type| self.type_to_zod(ty);
+        match ty {
+            Type::Lit(lit) => z("literal", vec![Expr::Lit(lit.clone())]),
+            Type::Ref(name, args) => match (name.as_ref(), &args[..]) {
+                ("number", [])
+                | ("string", [])
+                | ("boolean", [])
+                | ("bigint", [])
+                | ("symbol", [])
+                | ("null", [])
+                | ("undefined", [])
+                | ("void", [])
+                | ("any", [])
+                | ("unknown", [])
+                | ("never", []) => z(name, vec![]),
+                ("object", []) => {
+                    z("object", vec![Expr::Object(vec![])]).method("passthrough", vec![])
+                }
+                ("Date", []) => z("date", vec![]),
+                ("Uint8Array", []) => z("instanceof", vec![Expr::ident(name.as_str())]),
+                // zod can't describe it, so trust the typescript
+                (JSON_VALUE, []) => custom(ty),
+                ("Array", [elem]) | ("ReadonlyArray", [elem]) => z("array", vec![to_zod(elem)]),
+                ("Readonly", [ty]) => to_zod(ty),
+                ("Record", [key, value]) => {
+                    z("record", vec![record_key(key, to_zod), to_zod(value)])
+                }
+                // `Partial<Record<K, V>>`: a zod record doesn't need every key
+                ("Partial", [ty @ Type::Ref(record, _)]) if record == "Record" => to_zod(ty),
+                // a flattened `Option<T>`: all of `T` or nothing
+                ("Partial", [ty]) => {
+                    to_zod(ty).method("or", vec![z("object", vec![Expr::Object(vec![])])])
+                }
+                ("Map", [key, value]) | ("ReadonlyMap", [key, value]) => {
+                    z("map", vec![to_zod(key), to_zod(value)])
+                }
+                (name, []) if self.is_type_param(name) => Expr::ident(schema_ident(name)),
+                (name, _) if !self.is_reference(name) => custom(ty),
+                _ => {
+                    let schema = Expr::ident(schema_ident(name));
+                    let schema = if args.is_empty() {
+                        schema
+                    } else {
+                        schema.call(args.iter().map(to_zod).collect())
+                    };
+                    z("lazy", vec![Expr::arrow(vec![], None, schema)])
+                }
+            },
+            Type::Array(elem) => z("array", vec![to_zod(elem)]),
+            Type::Readonly(ty) => to_zod(ty),
+            Type::Tuple(elems) => z(
+                "tuple",
+                vec![Expr::Array(elems.iter().map(to_zod).collect())],
+            ),
+            Type::Object(members) => object(members, to_zod),
+            Type::Union(types) => {
+                let (nullish, types): (Vec<_>, Vec<_>) = types
+                    .iter()
+                    .partition(|t| **t == Type::named("null") || **t == Type::named("undefined"));
+                let schema = union(types.into_iter().map(to_zod).collect());
+                // `T | null` => `T.nullable()`
+                nullish.iter().fold(schema, |schema, t| match t {
+                    Type::Ref(name, _) if name == "null" => schema.method("nullable", vec![]),
+                    _ => schema.method("optional", vec![]),
+                })
+            }
+            Type::Intersection(types) => {
+                let mut types = types.iter().map(to_zod);
+                let first = types.next().unwrap_or_else(|| z("unknown", vec![]));
+                types.fold(first, |a, b| a.method("and", vec![b]))
+            }
+            Type::Function(..) => z("function", vec![]),
+            // only in declarations we make ourselves
+            Type::Predicate(..)
+            | Type::Typeof(..)
+            | Type::Keyof(..)
+            | Type::IndexedAccess(..)
+            | Type::Raw(..) => z("any", vec![]),
+        }
+    }
+
+    /// the schema of an enum's variants: a `z.discriminatedUnion` if they are all
+    /// objects with the `tag` (internally or adjacently tagged), otherwise a `z.union`
+    pub(crate) fn variants_to_zod(&self, tag: Option<&str>, variants: &[Type]) -> Expr {
+        let options = variants
+            .iter()
+            .map(|v| self.type_to_zod(v))
+            .collect::<Vec<_>>();
+        let tagged = |v: &Type| match v {
+            Type::Object(members) => members
+                .iter()
+                .any(|m| matches!(m, Member::Property { key, .. } if Some(key.as_str()) == tag)),
+            _ => false,
+        };
+        match tag {
+            Some(tag) if variants.len() > 1 && variants.iter().all(tagged) => z(
+                "discriminatedUnion",
+                vec![Expr::str(tag), Expr::Array(options)],
+            ),
+            _ => union(options),
+        }
+    }
+
+    /// `z.nativeEnum(Color)` for a typescript enum, otherwise the literals
+    /// (`Color.Red` for a `const enum`, which has no object at runtime)
+    pub(crate) fn unit_enum_to_zod(&self, kind: &QuoteMakerKind, literals: &Type) -> Expr {
+        let name = self.type_name(&self.ident);
+        match kind {
+            QuoteMakerKind::Enum(..) | QuoteMakerKind::ConstObject(..) => {
+                z("nativeEnum", vec![Expr::ident(name)])
+            }
+            QuoteMakerKind::ConstEnum(members) => union(
+                members
+                    .iter()
+                    .map(|m| {
+                        z(
+                            "literal",
+                            vec![Expr::ident(name.as_str()).member(m.name.as_str())],
+                        )
+                    })
+                    .collect(),
+            ),
+            _ => self.type_to_zod(literals),
+        }
+    }
+}
@@ -12,39 +12,10 @@
 
 extern crate proc_macro;
 use quote::quote;
-use serde_derive_internals::{ast, Ctxt, Derive};
-use syn::DeriveInput;
-
-mod attrs;
-mod derive_enum;
-mod derive_struct;
-mod patch;
-mod tests;
-mod tots;
-mod utils;
-
-use attrs::Attrs;
-use utils::*;
-
-use patch::patch;
-
-// too many TokenStreams around! give it a different name
-type QuoteT = proc_macro2::TokenStream;
-
-struct QuoteMaker {
-    pub source: QuoteT,
-    /// enum factory quote token stream
-    pub enum_factory: Result<QuoteT, &'static str>,
-    /// enum handler quote token stream
-    pub enum_handler: Result<QuoteT, &'static str>,
-    pub kind: QuoteMakerKind,
-}
-
-enum QuoteMakerKind {
-    Object,
-    Enum,
-    Union,
-}
+use typescript_definitions_core::{
+    ident_from_str, json_value_definition_json, Dialect, QuoteT, Typescriptify, JSON_VALUE,
+    JSON_VALUE_DEFINITION,
+};
 
 /* #region helpers */
 
@@ -63,6 +34,24 @@ fn is_wasm32() -> bool {
     false
 }
 
+/// recompile when `typescript-definitions.toml` changes
+fn track_config(tsy: &Typescriptify) -> QuoteT {
+    match tsy.config.path {
+        Some(ref path) => {
+            let path = path.to_string_lossy();
+            quote!(
+                const _: &[u8] = include_bytes!(#path);
+            )
+        }
+        None => quote!(),
+    }
+}
+
+fn compile_errors(errors: Vec<syn::Error>) -> QuoteT {
+    let errors = errors.iter().map(syn::Error::to_compile_error);
+    quote!(#(#errors)*)
+}
+
 /// derive proc_macro to expose Typescript definitions to `wasm-bindgen`.
 ///
 /// Please see documentation at [crates.io](https://crates.io/crates/typescript-definitions).
@@ -83,7 +72,10 @@ pub fn derive_type_script_ify(input: proc_macro::TokenStream) -> proc_macro::Tok
 
 fn do_derive_typescript_definition(input: QuoteT) -> QuoteT {
     let tsy = Typescriptify::new(input);
-    let parsed = tsy.parse();
+    let mut parsed = tsy.parse();
+    if let Err(errors) = parsed.check() {
+        return compile_errors(errors);
+    }
     let export_source = parsed.export_type_definition_source();
     let export_string = format!(
         // we're still going to include the values so we can separate them out in an additional step for webpack
@@ -100,7 +92,10 @@ fn do_derive_typescript_definition(input: QuoteT) -> QuoteT {
 
     let export_ident = ident_from_str(&format!("TS_EXPORT_{}", name));
 
+    let track_config = track_config(&tsy);
     let mut q = quote! {
+        #track_config
+
         #[wasm_bindgen(typescript_custom_section)]
         pub const #export_ident : &'static str = #export_string;
     };
@@ -117,7 +112,7 @@ fn do_derive_typescript_definition(input: QuoteT) -> QuoteT {
         ));
     }
     if let Some("1") = option_env!("TFY_SHOW_CODE") {
-        eprintln!("{}", patch(&q.to_string()));
+        eprintln!("{}", q);
     }
 
     q
@@ -125,9 +120,39 @@ fn do_derive_typescript_definition(input: QuoteT) -> QuoteT {
 
 fn do_derive_type_script_ify(input: QuoteT) -> QuoteT {
     let tsy = Typescriptify::new(input);
-    let parsed = tsy.parse();
-    let export_source = parsed.export_type_definition_source();
-    let export_string = format!("{}\n{}", export_source.declarations, export_source.values);
+    let mut parsed = tsy.parse();
+    // only check serde's deserialize attributes when the `Input` type is wanted
+    let mut parsed_input = if tsy.wants_input() {
+        Some(tsy.parse_input())
+    } else {
+        None
+    };
+    let input_checked = parsed_input.as_mut().map_or(Ok(()), |p| p.check());
+    if let Err(errors) = parsed.check().and(input_checked) {
+        return compile_errors(errors);
+    }
+    let export_string = parsed.type_script_ify_source();
+    let export_name = &parsed.ident;
+    let export_input = parsed_input
+        .as_ref()
+        .map(|p| p.export_type_declaration_source())
+        .unwrap_or_default();
+    // the syntax trees, for the `_with` exports to print with another `TsFormat`
+    let export_ast = parsed.type_script_ify_json();
+    let export_input_ast = parsed_input
+        .as_ref()
+        .map(|p| p.export_type_declaration_json())
+        .unwrap_or_default();
+    let input_dependencies = parsed_input
+        .as_ref()
+        .map(|p| p.input_dependencies())
+        .unwrap_or_default();
+    let mut dependencies = parsed.dependencies();
+    for dep in parsed_input.iter().flat_map(|p| p.dependencies()) {
+        if !dependencies.contains(&dep) {
+            dependencies.push(dep);
+        }
+    }
     let ident = &tsy.ident;
 
     let (impl_generics, ty_generics, where_clause) = tsy.generics.split_for_impl();
@@ -159,380 +184,117 @@ fn do_derive_type_script_ify(input: QuoteT) -> QuoteT {
     } else {
         quote!()
     };
+    // the `[outputs]` of `typescript-definitions.toml`: a switched off output is an `Err`
+    let outputs = &tsy.config.outputs;
+    let type_script_guard = if outputs.guard {
+        let guard = parsed.export_type_guard_source();
+        quote!(Ok(::std::borrow::Cow::Borrowed(#guard)))
+    } else {
+        quote!(Err(
+            "`guard = false` in the [outputs] of typescript-definitions.toml"
+        ))
+    };
+    let type_script_zod = if outputs.zod {
+        let zod = parsed.export_type_zod_source();
+        quote!(Ok(::std::borrow::Cow::Borrowed(#zod)))
+    } else {
+        quote!(Err(
+            "`zod = false` in the [outputs] of typescript-definitions.toml"
+        ))
+    };
+    let (export_json_schema, export_openapi_schema) = if outputs.json_schema {
+        (
+            parsed.export_json_schema_source(Dialect::JsonSchema),
+            parsed.export_json_schema_source(Dialect::OpenApi),
+        )
+    } else {
+        (String::new(), String::new())
+    };
+    let json_schema = if outputs.json_schema {
+        quote!(
+            Ok(match dialect {
+                ::typescript_definitions::SchemaDialect::JsonSchema => ::std::borrow::Cow::Borrowed(#export_json_schema),
+                ::typescript_definitions::SchemaDialect::OpenApi => ::std::borrow::Cow::Borrowed(#export_openapi_schema),
+            })
+        )
+    } else {
+        quote!({
+            let _ = dialect;
+            Err("`json_schema = false` in the [outputs] of typescript-definitions.toml")
+        })
+    };
+    let type_script_input = if tsy.wants_input() {
+        quote!(
+            fn type_script_input() ->  ::std::borrow::Cow<'static,str> {
+                ::std::borrow::Cow::Borrowed(#export_input)
+            }
+        )
+    } else {
+        quote!()
+    };
+    // `serde_json::Value` fields need `JsonValue` exported too
+    let json_value = if dependencies.iter().any(|dep| dep == JSON_VALUE) {
+        let json_value_ast = json_value_definition_json();
+        quote!(::typescript_definitions::inventory::submit! {
+            ::typescript_definitions::TypeScriptifyRegistration {
+                name: #JSON_VALUE,
+                definition: #JSON_VALUE_DEFINITION,
+                definition_ast: #json_value_ast,
+                input: "",
+                input_ast: "",
+                json_schema: "",
+                openapi_schema: "",
+                dependencies: &[],
+                input_dependencies: &[],
+            }
+        })
+    } else {
+        quote!()
+    };
+    let track_config = track_config(&tsy);
     let ret = quote! {
+        #track_config
 
         impl #impl_generics ::typescript_definitions::TypeScriptifyTrait for #ident #ty_generics #where_clause {
             fn type_script_ify() ->  ::std::borrow::Cow<'static,str> {
                 ::std::borrow::Cow::Borrowed(#export_string)
             }
+            fn type_script_guard() -> Result<::std::borrow::Cow<'static,str>, &'static str> {
+                #type_script_guard
+            }
+            fn type_script_zod() -> Result<::std::borrow::Cow<'static,str>, &'static str> {
+                #type_script_zod
+            }
+            fn json_schema(dialect: ::typescript_definitions::SchemaDialect) -> Result<::std::borrow::Cow<'static,str>, &'static str> {
+                #json_schema
+            }
+            #type_script_input
+            fn dependencies() -> &'static [&'static str] {
+                &[#(#dependencies),*]
+            }
             #type_script_enum_factory
             #type_script_enum_handlers
         }
 
-    };
-    if let Some("1") = option_env!("TFY_SHOW_CODE") {
-        eprintln!("{}", patch(&ret.to_string()));
-    }
-
-    ret
-}
-
-/* #endregion helpers */
-
-pub(crate) struct Typescriptify {
-    ident: syn::Ident,
-    generics: syn::Generics,
-    input: DeriveInput,
-}
-
-impl Typescriptify {
-    pub fn new(input: QuoteT) -> Self {
-        let input: DeriveInput = syn::parse2(input).unwrap();
-
-        let cx = Ctxt::new();
-
-        let mut attrs = attrs::Attrs::new();
-        attrs.push_doc_comment(&input.attrs);
-        attrs.push_attrs(&input.ident, &input.attrs, Some(&cx));
-
-        let container = ast::Container::from_ast(&cx, &input, Derive::Serialize)
-            .expect("container was derived from AST");
-
-        // must track this in case of errors so we can check them
-        // if we don't consume the errors, we'll get an "unhandled errors" panic whether or not there were errors
-        cx.check().unwrap();
-
-        Self {
-            generics: container.generics.clone(),
-            ident: container.ident,
-            input,
-        }
-    }
-
-    fn parse(&self) -> TSOutput {
-        let input = &self.input;
-        let cx = Ctxt::new();
-
-        // collect and check #[ts(...attrs)]
-        let attrs = {
-            let mut attrs = attrs::Attrs::new();
-            attrs.push_doc_comment(&input.attrs);
-            attrs.push_attrs(&input.ident, &input.attrs, Some(&cx));
-            attrs
-        };
-
-        let container = ast::Container::from_ast(&cx, &input, Derive::Serialize)
-            .expect("container was derived from AST");
-
-        let (typescript, pctxt) = {
-            let pctxt = ParseContext {
-                ctxt: Some(cx),
-                global_attrs: attrs,
-                ident: container.ident.clone(),
-            };
-
-            let typescript = match container.data {
-                ast::Data::Enum(ref variants) => pctxt.derive_enum(variants, &container),
-                ast::Data::Struct(style, ref fields) => {
-                    pctxt.derive_struct(style, fields, &container)
-                }
-            };
-
-            // erase serde context
-            (typescript, pctxt)
-        };
-
-        TSOutput {
-            ident: patch(&container.ident.to_string()).into(),
-            pctxt,
-            q_maker: typescript,
-        }
-    }
-}
-
-struct TSOutput {
-    ident: String,
-    pctxt: ParseContext,
-    q_maker: QuoteMaker,
-}
-
-/// We have multiple kinds of exports that we need to differentiate between when using something like
-/// WASM Bindgen. For WASM-Bindgen, we have types needed for inputs (in the index.d.ts file), but we
-/// also want to provide helper values which cannot exist in the .d.ts file. For this, we have to separate
-/// what are simply type declarations, and what are helper values (functions, etc)
-struct TSDefinitions {
-    /// export type A = [number];
-    pub declarations: String,
-    /// export const a: A = [1];
-    pub values: String,
-}
-
-impl TSOutput {
-    fn export_type_handler_source(&self) -> Result<String, &'static str> {
-        self.q_maker
-            .enum_handler
-            .as_ref()
-            .map(|content| {
-                format!(
-                    "{}{}",
-                    self.pctxt.global_attrs.to_comment_str(),
-                    patch(&content.to_string())
-                )
-            })
-            .map_err(|e| *e)
-    }
-
-    fn export_type_factory_source(&self) -> Result<String, &'static str> {
-        self.q_maker
-            .enum_factory
-            .as_ref()
-            .map(|content| {
-                format!(
-                    "{}{}",
-                    self.pctxt.global_attrs.to_comment_str(),
-                    patch(&content.to_string())
-                )
-            })
-            .map_err(|e| *e)
-    }
-
-    fn export_type_definition_source(&self) -> TSDefinitions {
-        match self.q_maker.kind {
-            QuoteMakerKind::Enum => TSDefinitions {
-                declarations: format!(
-                    "{}export enum {} {}",
-                    self.pctxt.global_attrs.to_comment_str(),
-                    self.ident,
-                    patch(&self.q_maker.source.to_string())
-                ),
-                values: format!(
-                    "{}export enum {} {}",
-                    self.pctxt.global_attrs.to_comment_str(),
-                    self.ident,
-                    patch(&self.q_maker.source.to_string())
-                ),
-            },
-            QuoteMakerKind::Union => TSDefinitions {
-                declarations: format!(
-                    "{}export type {} = {}",
-                    self.pctxt.global_attrs.to_comment_str(),
-                    self.ident,
-                    patch(&self.q_maker.source.to_string()),
-                ),
-                values: format!(
-                    "{}\n{}",
-                    self.export_type_factory_source()
-                        .expect("factory exists for union"),
-                    self.export_type_handler_source()
-                        .expect("handler exists for union"),
-                ),
-            },
-            QuoteMakerKind::Object => TSDefinitions {
-                declarations: format!(
-                    "{}export type {} = {}",
-                    self.pctxt.global_attrs.to_comment_str(),
-                    self.ident,
-                    patch(&self.q_maker.source.to_string()),
-                ),
-                values: format!(
-                    "{}export const {} = (check: {}) => check\n",
-                    // check create function
-                    self.pctxt.global_attrs.to_comment_str(),
-                    self.ident,
-                    self.ident,
-                ),
-            },
-        }
-    }
-}
-
-fn return_type(rt: &syn::ReturnType) -> Option<syn::Type> {
-    match rt {
-        syn::ReturnType::Default => None, // e.g. undefined
-        syn::ReturnType::Type(_, tp) => Some(*tp.clone()),
-    }
-}
-
-// represents a typescript type T<A,B>
-struct TSType {
-    ident: syn::Ident,
-    args: Vec<syn::Type>,
-    path: Vec<syn::Ident>,          // full path
-    return_type: Option<syn::Type>, // only if function
-}
-
-impl TSType {
-    fn path(&self) -> Vec<String> {
-        self.path.iter().map(|i| i.to_string()).collect() // hold the memory
-    }
-}
-
-fn last_path_element(path: &syn::Path) -> Option<TSType> {
-    let fullpath = path
-        .segments
-        .iter()
-        .map(|s| s.ident.clone())
-        .collect::<Vec<_>>();
-    match path.segments.last() {
-        Some(t) => {
-            let ident = t.ident.clone();
-            let args = match &t.arguments {
-                syn::PathArguments::AngleBracketed(ref path) => &path.args,
-                // closures Fn(A,B) -> C
-                syn::PathArguments::Parenthesized(ref path) => {
-                    let args: Vec<_> = path.inputs.iter().cloned().collect();
-                    let ret = return_type(&path.output);
-                    return Some(TSType {
-                        ident,
-                        args,
-                        path: fullpath,
-                        return_type: ret,
-                    });
-                }
-                syn::PathArguments::None => {
-                    return Some(TSType {
-                        ident,
-                        args: vec![],
-                        path: fullpath,
-                        return_type: None,
-                    });
-                }
-            };
-            // ignore lifetimes
-            let args = args
-                .iter()
-                .filter_map(|p| match p {
-                    syn::GenericArgument::Type(t) => Some(t),
-                    syn::GenericArgument::Binding(t) => Some(&t.ty),
-                    syn::GenericArgument::Constraint(..) => None,
-                    syn::GenericArgument::Const(..) => None,
-                    _ => None, // lifetimes, expr, constraints A : B ... skip!
-                })
-                .cloned()
-                .collect::<Vec<_>>();
-
-            Some(TSType {
-                ident,
-                path: fullpath,
-                args,
-                return_type: None,
-            })
-        }
-        None => None,
-    }
-}
-
-pub(crate) struct FieldContext<'a> {
-    pub ctxt: &'a ParseContext,    // global parse context
-    pub field: &'a ast::Field<'a>, // field being parsed
-    pub attrs: Attrs,              // field attributes
-}
-
-impl<'a> FieldContext<'a> {
-    pub fn get_path(&self, ty: &syn::Type) -> Option<TSType> {
-        use syn::Type::Path;
-        use syn::TypePath;
-        match ty {
-            Path(TypePath { path, .. }) => last_path_element(&path),
-            _ => None,
-        }
-    }
-}
-
-pub(crate) struct ParseContext {
-    ctxt: Option<Ctxt>,  // serde parse context for error reporting
-    global_attrs: Attrs, // global #[ts(...)] attributes
-    ident: syn::Ident,   // name of enum struct
-}
-
-impl Drop for ParseContext {
-    fn drop(&mut self) {
-        if !std::thread::panicking() {
-            // must track this in case of errors so we can check them
-            // if we don't consume the errors, we'll get an "unhandled errors" panic whether or not there were errors
-            if let Some(ctxt) = self.ctxt.take() {
-                ctxt.check().expect("no errors")
+        ::typescript_definitions::inventory::submit! {
+            ::typescript_definitions::TypeScriptifyRegistration {
+                name: #export_name,
+                definition: #export_string,
+                definition_ast: #export_ast,
+                input: #export_input,
+                input_ast: #export_input_ast,
+                json_schema: #export_json_schema,
+                openapi_schema: #export_openapi_schema,
+                dependencies: &[#(#dependencies),*],
+                input_dependencies: &[#(#input_dependencies),*],
             }
         }
-    }
-}
-
-impl<'a> ParseContext {
-    // Some helpers
 
-    fn err_msg<A: quote::ToTokens>(&self, tokens: A, msg: &str) {
-        if let Some(ref ctxt) = self.ctxt {
-            ctxt.error_spanned_by(tokens, msg);
-        } else {
-            panic!("{}", msg.to_string())
-        }
-    }
-
-    /// returns { #ty } of
-    fn field_to_ts(&self, field: &ast::Field<'a>) -> QuoteT {
-        let attrs = Attrs::from_field(field, self.ctxt.as_ref());
-        // if user has provided a type ... use that
-        if attrs.ts_type.is_some() {
-            use std::str::FromStr;
-            let s = attrs.ts_type.unwrap();
-            return match QuoteT::from_str(&s) {
-                Ok(tokens) => tokens,
-                Err(..) => {
-                    self.err_msg(
-                        field.original,
-                        &format!("{}: can't parse type {}", self.ident, s),
-                    );
-                    quote!()
-                }
-            };
-        }
-
-        let fc = FieldContext {
-            attrs,
-            ctxt: &self,
-            field,
-        };
-        if let Some(ref ty) = fc.attrs.ts_as {
-            fc.type_to_ts(ty)
-        } else {
-            fc.type_to_ts(&field.ty)
-        }
-    }
-
-    /// returns { #field_name: #ty }
-    fn derive_field(&self, field: &ast::Field<'a>) -> QuoteT {
-        let field_name = field.attrs.name().serialize_name(); // use serde name instead of field.member
-        let field_name = ident_from_str(&field_name);
-        let ty = self.field_to_ts(&field);
-        let comment = Attrs::from_field(field, self.ctxt.as_ref()).to_comment_attrs();
-        quote!(#(#comment)* #field_name: #ty)
-    }
-
-    fn derive_fields(
-        &'a self,
-        fields: &'a [&'a ast::Field<'a>],
-    ) -> impl Iterator<Item = QuoteT> + 'a {
-        fields.iter().map(move |f| self.derive_field(f))
-    }
-
-    fn derive_field_tuple(
-        &'a self,
-        fields: &'a [&'a ast::Field<'a>],
-    ) -> impl Iterator<Item = QuoteT> + 'a {
-        fields.iter().map(move |f| self.field_to_ts(f))
+        #json_value
+    };
+    if let Some("1") = option_env!("TFY_SHOW_CODE") {
+        eprintln!("{}", ret);
     }
 
-    fn check_flatten(&self, fields: &[&'a ast::Field<'a>], ast_container: &ast::Container) -> bool {
-        let has_flatten = fields.iter().any(|f| f.attrs.flatten()); // .any(|f| f);
-        if has_flatten {
-            self.err_msg(
-                &self.ident,
-                &format!(
-                    "{}: #[serde(flatten)] does not work for typescript-definitions.",
-                    ast_container.ident
-                ),
-            );
-        };
-        has_flatten
-    }
+    ret
 }